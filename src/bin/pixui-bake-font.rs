@@ -0,0 +1,47 @@
+//! Bakes a BDF, PCF or PSF font into the format read by `StaticFont`. Gzipped fonts,
+//! like `.pcf.gz` and `.psf.gz`, have to be gunzipped first.
+//!
+//! ```text
+//! pixui-bake-font <input> <output>
+//! ```
+
+use std::{env, error::Error, fs, io::BufReader, process::ExitCode};
+
+use pixui::gfx::{BdfFont, Font, PcfFont, PsfFont, StaticFont};
+
+fn load(file: &[u8]) -> Result<Box<dyn Font>, Box<dyn Error>> {
+    if file.starts_with(b"\x1f\x8b") {
+        return Err("font is gzipped, gunzip it first".into());
+    }
+    Ok(if file.starts_with(b"STARTFONT") {
+        Box::new(BdfFont::new(&mut BufReader::new(file))?)
+    } else if file.starts_with(b"\x01fcp") {
+        Box::new(PcfFont::new(&mut &file[..])?)
+    } else {
+        Box::new(PsfFont::new(&mut &file[..])?)
+    })
+}
+
+fn bake(input: &str, output: &str) -> Result<(), Box<dyn Error>> {
+    let font = load(&fs::read(input)?)?;
+    let baked = StaticFont::bake(&*font, '\0'..=char::MAX);
+    let glyphs = StaticFont::new(&baked)?.len();
+    fs::write(output, &baked)?;
+    println!("{output}: {glyphs} glyphs in {} bytes", baked.len());
+    Ok(())
+}
+
+fn main() -> ExitCode {
+    let args: Vec<_> = env::args().collect();
+    let [_, input, output] = &args[..] else {
+        eprintln!("usage: pixui-bake-font <input> <output>");
+        return ExitCode::FAILURE;
+    };
+    match bake(input, output) {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(err) => {
+            eprintln!("{input}: {err}");
+            ExitCode::FAILURE
+        }
+    }
+}
@@ -0,0 +1,149 @@
+use std::borrow::{Borrow, BorrowMut};
+
+use crate::gfx::{BlendMode, Color, Point, ReadSurface, Rect, Scalar, Size, Surface, WriteSurface};
+
+/// A 1-bit-per-pixel image.
+///
+/// Rows are `stride` bytes apart and each is packed most-significant-bit first, so the
+/// leftmost pixel of a row is the high bit of its first byte.
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Hash)]
+pub struct Bitmap<S> {
+    bits: S,
+    stride: usize,
+    size: Size,
+}
+
+pub type VecBitmap = Bitmap<Vec<u8>>;
+
+impl VecBitmap {
+    #[inline]
+    pub fn from_size(size: Size) -> Self {
+        let stride = Self::min_stride(size.width);
+        Self::new(vec![0; stride * size.height.max(0) as usize], stride, size)
+    }
+}
+
+impl<S> Bitmap<S> {
+    #[inline]
+    pub const fn new(bits: S, stride: usize, size: Size) -> Self {
+        Self { bits, stride, size }
+    }
+
+    /// The smallest stride that can hold a row `width` pixels wide.
+    #[inline]
+    pub const fn min_stride(width: Scalar) -> usize {
+        if width <= 0 {
+            0
+        } else {
+            (width as usize).div_ceil(8)
+        }
+    }
+
+    #[inline]
+    pub const fn size(&self) -> Size {
+        self.size
+    }
+
+    #[inline]
+    pub const fn stride(&self) -> usize {
+        self.stride
+    }
+}
+
+impl<S> Bitmap<S>
+where
+    S: Borrow<[u8]>,
+{
+    #[inline]
+    pub fn bits(&self) -> &[u8] {
+        self.bits.borrow()
+    }
+
+    #[inline]
+    pub fn view(&self) -> Bitmap<&[u8]> {
+        Bitmap::new(self.bits.borrow(), self.stride, self.size)
+    }
+
+    /// The bytes of row `y`, including any padding at the end.
+    #[inline]
+    pub fn row(&self, y: Scalar) -> &[u8] {
+        let start = y as usize * self.stride;
+        &self.bits.borrow()[start..start + self.stride]
+    }
+
+    #[inline]
+    pub fn get(&self, point: Point) -> bool {
+        if point.x < 0 || point.x >= self.size.width {
+            return false;
+        }
+        if point.y < 0 || point.y >= self.size.height {
+            return false;
+        }
+        self.row(point.y)[point.x as usize / 8] & (0b1000_0000 >> (point.x % 8)) != 0
+    }
+}
+
+impl<S> Bitmap<S>
+where
+    S: BorrowMut<[u8]>,
+{
+    #[inline]
+    pub fn row_mut(&mut self, y: Scalar) -> &mut [u8] {
+        let start = y as usize * self.stride;
+        &mut self.bits.borrow_mut()[start..start + self.stride]
+    }
+
+    #[inline]
+    pub fn set(&mut self, point: Point, value: bool) {
+        if point.x < 0 || point.x >= self.size.width {
+            return;
+        }
+        if point.y < 0 || point.y >= self.size.height {
+            return;
+        }
+        let offset = point.y as usize * self.stride + point.x as usize / 8;
+        let mask = 0b1000_0000 >> (point.x % 8);
+        let byte = &mut self.bits.borrow_mut()[offset];
+        if value {
+            *byte |= mask;
+        } else {
+            *byte &= !mask;
+        }
+    }
+}
+
+impl<S> Surface for Bitmap<S> {
+    #[inline]
+    fn bounds(&self) -> Rect {
+        Rect::sized(self.size)
+    }
+}
+
+impl<S> ReadSurface for Bitmap<S>
+where
+    S: Borrow<[u8]>,
+{
+    #[inline]
+    fn read(&self, point: Point) -> Option<Color> {
+        if !self.bounds().contains(point) {
+            return None;
+        }
+        if self.get(point) {
+            Some(Color::WHITE)
+        } else {
+            Some(Color::TRANSPARENT)
+        }
+    }
+}
+
+impl<S> WriteSurface for Bitmap<S>
+where
+    S: BorrowMut<[u8]>,
+{
+    /// Sets the pixel for any color that isn't fully transparent after blending.
+    fn write(&mut self, point: Point, color: Color, blend: BlendMode) {
+        if let Some(dst) = self.read(point) {
+            self.set(point, blend.blend(color, dst).alpha != 0);
+        }
+    }
+}
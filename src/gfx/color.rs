@@ -1,7 +1,21 @@
-#[derive(Copy, Clone, Debug)]
+use std::{
+    error::Error,
+    fmt::{self, Display, Formatter},
+    str::FromStr,
+};
+
+use crate::gfx::{linear_to_srgb, srgb_to_linear};
+
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Hash)]
 pub enum BlendMode {
+    /// Overwrites the destination.
     None,
+    /// Overwrites the destination unless the source is fully transparent.
     Blend,
+    /// Composites the source over the destination by its alpha, mixing sRGB values.
+    Alpha,
+    /// Composites the source over the destination by its alpha, mixing in linear light.
+    LinearAlpha,
 }
 
 impl BlendMode {
@@ -16,25 +30,41 @@ impl BlendMode {
                     src
                 }
             }
+            Self::Alpha => over(
+                src,
+                dst,
+                |c| c as u16 * 257,
+                |l| ((l as u32 + 128) / 257) as u8,
+            ),
+            Self::LinearAlpha => over(src, dst, srgb_to_linear, linear_to_srgb),
         }
     }
+}
 
-    #[inline]
-    pub(crate) fn blend_channel(&self, src: u8, src_alpha: u8, dst: u8) -> u8 {
-        match self {
-            Self::None => src,
-            Self::Blend => {
-                if src_alpha == 0 {
-                    dst
-                } else {
-                    src
-                }
-            }
-        }
+#[inline]
+fn over(src: Color, dst: Color, decode: fn(u8) -> u16, encode: fn(u16) -> u8) -> Color {
+    if src.alpha == 255 || dst.alpha == 0 {
+        return src;
     }
+    if src.alpha == 0 {
+        return dst;
+    }
+    let src_alpha = src.alpha as u64;
+    let dst_weight = dst.alpha as u64 * (255 - src_alpha);
+    let alpha = src_alpha * 255 + dst_weight;
+    let mix = |src: u8, dst: u8| {
+        let mixed = decode(src) as u64 * src_alpha * 255 + decode(dst) as u64 * dst_weight;
+        encode((mixed / alpha) as u16)
+    };
+    Color::new(
+        mix(src.red, dst.red),
+        mix(src.green, dst.green),
+        mix(src.blue, dst.blue),
+        ((alpha + 127) / 255) as u8,
+    )
 }
 
-#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Hash)]
 pub struct Color {
     pub(crate) red: u8,
     pub(crate) green: u8,
@@ -50,8 +80,25 @@ impl Default for Color {
 }
 
 impl Color {
+    pub const TRANSPARENT: Self = Self::new(0, 0, 0, 0);
+
+    // The 16 basic CSS colors
     pub const BLACK: Self = Self::opaque(0, 0, 0);
+    pub const SILVER: Self = Self::opaque(192, 192, 192);
+    pub const GRAY: Self = Self::opaque(128, 128, 128);
     pub const WHITE: Self = Self::opaque(255, 255, 255);
+    pub const MAROON: Self = Self::opaque(128, 0, 0);
+    pub const RED: Self = Self::opaque(255, 0, 0);
+    pub const PURPLE: Self = Self::opaque(128, 0, 128);
+    pub const FUCHSIA: Self = Self::opaque(255, 0, 255);
+    pub const GREEN: Self = Self::opaque(0, 128, 0);
+    pub const LIME: Self = Self::opaque(0, 255, 0);
+    pub const OLIVE: Self = Self::opaque(128, 128, 0);
+    pub const YELLOW: Self = Self::opaque(255, 255, 0);
+    pub const NAVY: Self = Self::opaque(0, 0, 128);
+    pub const BLUE: Self = Self::opaque(0, 0, 255);
+    pub const TEAL: Self = Self::opaque(0, 128, 128);
+    pub const AQUA: Self = Self::opaque(0, 255, 255);
 
     #[inline]
     pub const fn new(red: u8, green: u8, blue: u8, alpha: u8) -> Self {
@@ -87,4 +134,220 @@ impl Color {
     pub const fn alpha(&self) -> u8 {
         self.alpha
     }
+
+    #[inline]
+    pub const fn with_alpha(&self, alpha: u8) -> Self {
+        Self::new(self.red, self.green, self.blue, alpha)
+    }
+
+    /// Builds an opaque color from a hue in degrees and saturation and value in `0..=1`.
+    pub fn from_hsv(hue: f32, saturation: f32, value: f32) -> Self {
+        let saturation = saturation.clamp(0.0, 1.0);
+        let value = value.clamp(0.0, 1.0);
+        let chroma = value * saturation;
+        Self::from_hue(hue, chroma, value - chroma)
+    }
+
+    /// Returns the hue in degrees and the saturation and value in `0..=1`.
+    pub fn to_hsv(&self) -> (f32, f32, f32) {
+        let (hue, chroma, _, max) = self.hue_chroma();
+        let saturation = if max == 0.0 { 0.0 } else { chroma / max };
+        (hue, saturation, max)
+    }
+
+    /// Builds an opaque color from a hue in degrees and saturation and lightness in `0..=1`.
+    pub fn from_hsl(hue: f32, saturation: f32, lightness: f32) -> Self {
+        let saturation = saturation.clamp(0.0, 1.0);
+        let lightness = lightness.clamp(0.0, 1.0);
+        let chroma = (1.0 - (2.0 * lightness - 1.0).abs()) * saturation;
+        Self::from_hue(hue, chroma, lightness - chroma / 2.0)
+    }
+
+    /// Returns the hue in degrees and the saturation and lightness in `0..=1`.
+    pub fn to_hsl(&self) -> (f32, f32, f32) {
+        let (hue, chroma, min, max) = self.hue_chroma();
+        let lightness = (max + min) / 2.0;
+        let saturation = if lightness == 0.0 || lightness == 1.0 {
+            0.0
+        } else {
+            chroma / (1.0 - (2.0 * lightness - 1.0).abs())
+        };
+        (hue, saturation, lightness)
+    }
+
+    fn from_hue(hue: f32, chroma: f32, min: f32) -> Self {
+        let sector = hue.rem_euclid(360.0) / 60.0;
+        let x = chroma * (1.0 - (sector % 2.0 - 1.0).abs());
+        let (red, green, blue) = match sector as u8 {
+            0 => (chroma, x, 0.0),
+            1 => (x, chroma, 0.0),
+            2 => (0.0, chroma, x),
+            3 => (0.0, x, chroma),
+            4 => (x, 0.0, chroma),
+            _ => (chroma, 0.0, x),
+        };
+        Self::opaque(
+            unit_to_channel(red + min),
+            unit_to_channel(green + min),
+            unit_to_channel(blue + min),
+        )
+    }
+
+    fn hue_chroma(&self) -> (f32, f32, f32, f32) {
+        let red = channel_to_unit(self.red);
+        let green = channel_to_unit(self.green);
+        let blue = channel_to_unit(self.blue);
+        let max = red.max(green).max(blue);
+        let min = red.min(green).min(blue);
+        let chroma = max - min;
+        let hue = if chroma == 0.0 {
+            0.0
+        } else if max == red {
+            60.0 * ((green - blue) / chroma).rem_euclid(6.0)
+        } else if max == green {
+            60.0 * ((blue - red) / chroma + 2.0)
+        } else {
+            60.0 * ((red - green) / chroma + 4.0)
+        };
+        (hue, chroma, min, max)
+    }
+
+    /// Linearly interpolates every channel (including alpha) towards `other`.
+    ///
+    /// `t` is clamped to `0..=1`, where 0 gives `self` and 1 gives `other`.
+    pub fn lerp(&self, other: Self, t: f32) -> Self {
+        let t = t.clamp(0.0, 1.0);
+        let mix = |a: u8, b: u8| (a as f32 + (b as f32 - a as f32) * t).round() as u8;
+        Self::new(
+            mix(self.red, other.red),
+            mix(self.green, other.green),
+            mix(self.blue, other.blue),
+            mix(self.alpha, other.alpha),
+        )
+    }
+
+    /// Like [`Color::lerp`], but interpolates the color channels in linear light.
+    ///
+    /// Gradients between saturated colors stay bright through the middle instead of
+    /// passing through a muddy dark band.
+    pub fn lerp_linear(&self, other: Self, t: f32) -> Self {
+        let t = t.clamp(0.0, 1.0);
+        let mix = |a: u8, b: u8| {
+            let a = srgb_to_linear(a) as f32;
+            let b = srgb_to_linear(b) as f32;
+            linear_to_srgb((a + (b - a) * t).round() as u16)
+        };
+        Self::new(
+            mix(self.red, other.red),
+            mix(self.green, other.green),
+            mix(self.blue, other.blue),
+            (self.alpha as f32 + (other.alpha as f32 - self.alpha as f32) * t).round() as u8,
+        )
+    }
+
+    /// Mixes the color towards white by `amount` in `0..=1`, keeping its alpha.
+    #[inline]
+    pub fn lighten(&self, amount: f32) -> Self {
+        self.lerp(Self::WHITE.with_alpha(self.alpha), amount)
+    }
+
+    /// Mixes the color towards black by `amount` in `0..=1`, keeping its alpha.
+    #[inline]
+    pub fn darken(&self, amount: f32) -> Self {
+        self.lerp(Self::BLACK.with_alpha(self.alpha), amount)
+    }
+
+    /// Returns the gray with the same luma as this color, keeping its alpha.
+    pub fn grayscale(&self) -> Self {
+        let luma =
+            0.2126 * self.red as f32 + 0.7152 * self.green as f32 + 0.0722 * self.blue as f32;
+        let luma = luma.round() as u8;
+        Self::new(luma, luma, luma, self.alpha)
+    }
+
+    /// The relative luminance as defined by WCAG 2, from 0 for black to 1 for white.
+    pub fn luminance(&self) -> f32 {
+        let linear = |channel: u8| srgb_to_linear(channel) as f32 / u16::MAX as f32;
+        0.2126 * linear(self.red) + 0.7152 * linear(self.green) + 0.0722 * linear(self.blue)
+    }
+
+    /// The WCAG 2 contrast ratio between two colors, from 1 to 21.
+    pub fn contrast_ratio(&self, other: Self) -> f32 {
+        let a = self.luminance();
+        let b = other.luminance();
+        (a.max(b) + 0.05) / (a.min(b) + 0.05)
+    }
+}
+
+#[inline]
+fn channel_to_unit(channel: u8) -> f32 {
+    channel as f32 / 255.0
+}
+
+#[inline]
+fn unit_to_channel(unit: f32) -> u8 {
+    (unit.clamp(0.0, 1.0) * 255.0).round() as u8
+}
+
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum ParseColorError {
+    MissingHash,
+    InvalidLength(usize),
+    InvalidDigit(char),
+}
+
+impl Display for ParseColorError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::MissingHash => write!(f, "color must start with '#'"),
+            Self::InvalidLength(len) => {
+                write!(f, "color must have 3, 4, 6 or 8 hex digits, not {len}")
+            }
+            Self::InvalidDigit(c) => write!(f, "invalid hex digit {c:?} in color"),
+        }
+    }
+}
+
+impl Error for ParseColorError {}
+
+impl FromStr for Color {
+    type Err = ParseColorError;
+
+    /// Parses `#rgb`, `#rgba`, `#rrggbb` or `#rrggbbaa`.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let digits = s.strip_prefix('#').ok_or(ParseColorError::MissingHash)?;
+        let mut nibbles = [0u8; 8];
+        let mut len = 0;
+        for c in digits.chars() {
+            let nibble = c.to_digit(16).ok_or(ParseColorError::InvalidDigit(c))?;
+            if len < nibbles.len() {
+                nibbles[len] = nibble as u8;
+            }
+            len += 1;
+        }
+        match len {
+            3 | 4 => {
+                let short = |i: usize| nibbles[i] << 4 | nibbles[i];
+                let alpha = if len == 4 { short(3) } else { 255 };
+                Ok(Self::new(short(0), short(1), short(2), alpha))
+            }
+            6 | 8 => {
+                let long = |i: usize| nibbles[i * 2] << 4 | nibbles[i * 2 + 1];
+                let alpha = if len == 8 { long(3) } else { 255 };
+                Ok(Self::new(long(0), long(1), long(2), alpha))
+            }
+            _ => Err(ParseColorError::InvalidLength(len)),
+        }
+    }
+}
+
+impl Display for Color {
+    /// Formats as `#rrggbb`, or `#rrggbbaa` if the color isn't opaque.
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "#{:02x}{:02x}{:02x}", self.red, self.green, self.blue)?;
+        if self.alpha != 255 {
+            write!(f, "{:02x}", self.alpha)?;
+        }
+        Ok(())
+    }
 }
@@ -0,0 +1,203 @@
+use std::mem;
+
+use crate::gfx::{BlendMode, Color, Point, Rect, Size, Surface, WriteSurface};
+
+#[derive(Debug, Default)]
+pub struct FrameDiff {
+    size: Size,
+    previous: Option<Vec<Color>>,
+    current: Vec<Color>,
+}
+
+impl FrameDiff {
+    #[inline]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Forgets the previous frame so that the next one is reported as entirely dirty.
+    #[inline]
+    pub fn reset(&mut self) {
+        self.previous = None;
+    }
+
+    pub fn wrap<'a>(&'a mut self, surface: &'a mut dyn WriteSurface) -> DiffSurface<'a> {
+        let size = surface.bounds().size;
+        if size != self.size {
+            self.size = size;
+            self.previous = None;
+        }
+        match &self.previous {
+            Some(previous) => self.current.clone_from(previous),
+            None => {
+                self.current.clear();
+                self.current
+                    .resize((size.width * size.height) as usize, Color::BLACK);
+            }
+        }
+        DiffSurface {
+            surface,
+            diff: self,
+        }
+    }
+}
+
+pub struct DiffSurface<'a> {
+    surface: &'a mut dyn WriteSurface,
+    diff: &'a mut FrameDiff,
+}
+
+impl<'a> DiffSurface<'a> {
+    /// Ends the frame and returns the rects covering every pixel whose color differs
+    /// from the previous frame.
+    ///
+    /// Changed pixels are collected into horizontal runs, and runs spanning the same
+    /// columns on consecutive rows are merged. The rects never overlap and never cover
+    /// an unchanged pixel.
+    pub fn finish(self) -> Vec<Rect> {
+        let diff = self.diff;
+        let size = diff.size;
+        let mut dirty = Vec::new();
+
+        let previous = match diff.previous.take() {
+            Some(previous) => previous,
+            None => {
+                if size.width > 0 && size.height > 0 {
+                    dirty.push(Rect::sized(size));
+                }
+                diff.previous = Some(diff.current.clone());
+                return dirty;
+            }
+        };
+
+        // rects that ended on the previous row and may still grow downward
+        let mut open: Vec<usize> = Vec::new();
+        let mut next_open = Vec::new();
+        for y in 0..size.height {
+            let row = (y * size.width) as usize;
+            let mut x = 0;
+            while x < size.width {
+                let offset = row + x as usize;
+                if previous[offset] == diff.current[offset] {
+                    x += 1;
+                    continue;
+                }
+                let start = x;
+                while x < size.width && previous[row + x as usize] != diff.current[row + x as usize]
+                {
+                    x += 1;
+                }
+                let run = Rect::new(Point::new(start, y), Size::new(x - start, 1));
+                let extends = open
+                    .iter()
+                    .copied()
+                    .find(|&i| dirty[i].left() == run.left() && dirty[i].width() == run.width());
+                match extends {
+                    Some(i) => {
+                        dirty[i].size.height += 1;
+                        next_open.push(i);
+                    }
+                    None => {
+                        next_open.push(dirty.len());
+                        dirty.push(run);
+                    }
+                }
+            }
+            open.clear();
+            open.append(&mut next_open);
+        }
+
+        // the current frame becomes the previous one, and the old buffer is reused
+        diff.previous = Some(mem::replace(&mut diff.current, previous));
+        dirty
+    }
+}
+
+impl<'a> Surface for DiffSurface<'a> {
+    #[inline]
+    fn bounds(&self) -> Rect {
+        self.surface.bounds()
+    }
+}
+
+impl<'a> WriteSurface for DiffSurface<'a> {
+    fn write(&mut self, point: Point, color: Color, blend: BlendMode) {
+        let size = self.diff.size;
+        if point.x < 0 || point.x >= size.width {
+            return;
+        }
+        if point.y < 0 || point.y >= size.height {
+            return;
+        }
+        let offset = (point.x + point.y * size.width) as usize;
+        let dst = &mut self.diff.current[offset];
+        *dst = blend.blend(color, *dst);
+        self.surface.write(point, color, blend);
+    }
+
+    #[inline]
+    fn begin_group(&mut self, bounds: Rect) {
+        self.surface.begin_group(bounds);
+    }
+
+    #[inline]
+    fn end_group(&mut self) {
+        self.surface.end_group();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::gfx::VecBitmap;
+
+    fn fill(surface: &mut dyn WriteSurface, rect: Rect, color: Color) {
+        for y in rect.top()..=rect.bottom() {
+            for x in rect.left()..=rect.right() {
+                surface.write(Point::new(x, y), color, BlendMode::None);
+            }
+        }
+    }
+
+    fn frame(diff: &mut FrameDiff, blocks: &[(Rect, Color)]) -> Vec<Rect> {
+        let mut bitmap = VecBitmap::from_size(Size::new(16, 12));
+        let mut surface = diff.wrap(&mut bitmap);
+        for &(rect, color) in blocks {
+            fill(&mut surface, rect, color);
+        }
+        surface.finish()
+    }
+
+    #[test]
+    fn finish() {
+        let mut diff = FrameDiff::new();
+        let a = Rect::new(Point::new(2, 1), Size::new(3, 2));
+        let b = Rect::new(Point::new(9, 4), Size::new(2, 3));
+        assert_eq!(
+            frame(&mut diff, &[(a, Color::WHITE)]),
+            [Rect::sized(Size::new(16, 12))]
+        );
+        // redrawing the same pixels in the same colors changes nothing
+        assert_eq!(frame(&mut diff, &[(a, Color::WHITE)]), []);
+
+        // two separate blocks, each merged from its rows
+        assert_eq!(
+            frame(&mut diff, &[(a, Color::RED), (b, Color::WHITE)]),
+            [a, b]
+        );
+
+        // an L shape is split rather than covering the unchanged corner
+        let top = Rect::new(Point::new(0, 8), Size::new(4, 1));
+        let stem = Rect::new(Point::new(0, 9), Size::new(2, 2));
+        let drawn = [
+            (a, Color::RED),
+            (b, Color::WHITE),
+            (top, Color::RED),
+            (stem, Color::RED),
+        ];
+        assert_eq!(frame(&mut diff, &drawn), [top, stem]);
+
+        diff.reset();
+        assert_eq!(frame(&mut diff, &drawn), [Rect::sized(Size::new(16, 12))]);
+    }
+}
@@ -0,0 +1,809 @@
+use std::{
+    io::{self, BufRead, BufReader, Lines, Read, Write},
+    str::{FromStr, SplitWhitespace},
+};
+
+use fxhash::FxHashMap;
+
+use crate::gfx::{
+    Font, FontError, FontErrorKind, Glyph, GlyphMap, MeasureCache, Point, Rect, Scalar, Size,
+    VecBitmap,
+};
+
+/// The largest width or height of a bounding box.
+const MAX_GLYPH_SIZE: Scalar = 4096;
+
+/// How forgiving [`BdfFont::with_mode`] is of fonts that don't follow the spec.
+#[derive(Copy, Clone, Debug, Default, Eq, PartialEq)]
+pub enum ParseMode {
+    /// Every deviation from the BDF 2.1 spec is an error.
+    Strict,
+    /// Unknown versions and missing sections are tolerated, malformed properties are
+    /// ignored, and malformed glyphs are skipped. Only I/O errors and truncated files
+    /// fail the whole font.
+    #[default]
+    Lenient,
+}
+
+pub struct BdfFont {
+    line_height: Scalar,
+    ascent: Scalar,
+    descent: Scalar,
+    default_char: Option<char>,
+    /// The lines between `STARTFONT` and `CHARS`, properties and comments included.
+    header: Vec<String>,
+    glyphs: GlyphMap,
+    info: FxHashMap<char, GlyphInfo>,
+    measure_cache: MeasureCache,
+}
+
+/// What a glyph has besides its bitmap, kept so that the font can be written back out.
+#[derive(Clone, Debug)]
+struct GlyphInfo {
+    name: String,
+    swidth: Option<Point>,
+}
+
+struct Parser<B> {
+    lines: Lines<B>,
+    line: usize,
+    pushed_back: Option<String>,
+    /// The comments skipped over while this is `Some`.
+    comments: Option<Vec<String>>,
+    mode: ParseMode,
+}
+
+impl<B: BufRead> Parser<B> {
+    #[inline]
+    fn error(&self, keyword: Option<&str>, kind: FontErrorKind) -> FontError {
+        FontError::new(self.line, keyword, kind)
+    }
+
+    /// Returns the next line with any content, skipping comments.
+    fn next_line(&mut self) -> Result<Option<String>, FontError> {
+        if let Some(line) = self.pushed_back.take() {
+            return Ok(Some(line));
+        }
+        while let Some(line) = self.lines.next() {
+            self.line += 1;
+            let line = line.map_err(|err| self.error(None, FontErrorKind::Io(err)))?;
+            let keyword = line.split_whitespace().next();
+            if keyword == Some("COMMENT") {
+                if let Some(comments) = &mut self.comments {
+                    comments.push(line);
+                }
+            } else if keyword.is_some() {
+                return Ok(Some(line));
+            }
+        }
+        self.line += 1;
+        Ok(None)
+    }
+
+    #[inline]
+    fn expect_line(&mut self) -> Result<String, FontError> {
+        self.next_line()?
+            .ok_or_else(|| self.error(None, FontErrorKind::UnexpectedEof))
+    }
+
+    /// Gives a line back so that the next call to [`Parser::next_line`] returns it again.
+    #[inline]
+    fn push_back(&mut self, line: String) {
+        self.pushed_back = Some(line);
+    }
+
+    /// Propagates the error in strict mode and drops it in lenient mode, unless it is
+    /// the kind of error that can't be skipped.
+    #[inline]
+    fn tolerate(&self, result: Result<(), FontError>) -> Result<(), FontError> {
+        match result {
+            Err(err) if self.mode == ParseMode::Strict || !err.is_recoverable() => Err(err),
+            _ => Ok(()),
+        }
+    }
+
+    fn value<T: FromStr>(
+        &self,
+        keyword: &str,
+        fields: &mut SplitWhitespace,
+    ) -> Result<T, FontError> {
+        let field = fields
+            .next()
+            .ok_or_else(|| self.error(Some(keyword), FontErrorKind::MissingValue))?;
+        field
+            .parse()
+            .map_err(|_| self.error(Some(keyword), FontErrorKind::InvalidValue(field.to_owned())))
+    }
+
+    fn bbox(&self, keyword: &str, fields: &mut SplitWhitespace) -> Result<Rect, FontError> {
+        let size = Size::new(self.value(keyword, fields)?, self.value(keyword, fields)?);
+        let origin = Point::new(self.value(keyword, fields)?, self.value(keyword, fields)?);
+        // anything bigger is a corrupt size that would only exhaust memory
+        if !(0..=MAX_GLYPH_SIZE).contains(&size.width)
+            || !(0..=MAX_GLYPH_SIZE).contains(&size.height)
+        {
+            return Err(self.error(
+                Some(keyword),
+                FontErrorKind::InvalidValue(format!("{}x{}", size.width, size.height)),
+            ));
+        }
+        Ok(Rect::new(origin, size))
+    }
+
+    /// Reads the rows following a `BITMAP` line.
+    ///
+    /// Each row holds hex digits for the whole width of the glyph, padded to a whole
+    /// number of bytes. In this example the bitmap data is 16-bits (16 pixels) wide:
+    /// ```text
+    /// BITMAP
+    /// C000
+    /// CDD0
+    /// 0000
+    /// ENDCHAR
+    /// ```
+    fn bitmap(&mut self, size: Size) -> Result<VecBitmap, FontError> {
+        let mut bitmap = VecBitmap::from_size(size);
+        let digits = bitmap.stride() * 2;
+        for y in 0..size.height {
+            let line = self.expect_line()?;
+            let row = line.trim();
+            // checked before the row's length, as rows of zero-width glyphs have no digits
+            if row == "ENDCHAR" {
+                if self.mode == ParseMode::Strict {
+                    return Err(self.error(None, FontErrorKind::InvalidBitmap));
+                }
+                // short bitmaps leave the remaining rows empty
+                self.push_back(line);
+                break;
+            }
+            if row.len() < digits && self.mode == ParseMode::Strict {
+                return Err(self.error(None, FontErrorKind::InvalidBitmap));
+            }
+            for (byte_x, byte_str) in row.as_bytes().chunks(2).take(digits / 2).enumerate() {
+                let byte = std::str::from_utf8(byte_str)
+                    .ok()
+                    .and_then(|byte_str| u8::from_str_radix(byte_str, 16).ok())
+                    .ok_or_else(|| self.error(None, FontErrorKind::InvalidBitmap))?;
+                // a lone trailing digit is the high nibble of the last byte
+                let byte = if byte_str.len() == 1 { byte << 4 } else { byte };
+                bitmap.row_mut(y)[byte_x] = byte;
+            }
+        }
+        Ok(bitmap)
+    }
+
+    /// Parses everything after a `STARTCHAR` line up to and including its `ENDCHAR`.
+    ///
+    /// Glyphs that have no codepoint are dropped.
+    fn glyph(
+        &mut self,
+        name: &str,
+        glyphs: &mut GlyphMap,
+        info: &mut FxHashMap<char, GlyphInfo>,
+        default_bbox: Rect,
+        default_width: Option<Scalar>,
+    ) -> Result<(), FontError> {
+        let strict = self.mode == ParseMode::Strict;
+        let mut encoding: Option<i64> = None;
+        let mut swidth = None;
+        let mut width = default_width;
+        let mut bbox = None;
+        let mut bitmap = None;
+        loop {
+            let line = self.expect_line()?;
+            let mut fields = line.split_whitespace();
+            let keyword = fields.next().unwrap_or_default();
+            match keyword {
+                "ENCODING" => {
+                    // The codepoint (may be negative)
+                    encoding = Some(self.value(keyword, &mut fields)?);
+                }
+                "SWIDTH" => {
+                    // only kept for writing the font back out, so it may be malformed
+                    let x = self.value(keyword, &mut fields).ok();
+                    let y = self.value(keyword, &mut fields).ok();
+                    swidth = x.zip(y).map(|(x, y)| Point::new(x, y));
+                }
+                "DWIDTH" => {
+                    width = Some(self.value(keyword, &mut fields)?);
+                }
+                "BBX" => {
+                    bbox = Some(self.bbox(keyword, &mut fields)?);
+                }
+                "BITMAP" => {
+                    if bbox.is_none() && strict {
+                        return Err(self.error(Some(keyword), FontErrorKind::MissingKeyword("BBX")));
+                    }
+                    bitmap = Some(self.bitmap(bbox.unwrap_or(default_bbox).size)?);
+                }
+                "ENDCHAR" => break,
+                "STARTCHAR" | "ENDFONT" => {
+                    let err = self.error(Some(keyword), FontErrorKind::MissingKeyword("ENDCHAR"));
+                    // let the caller see where the next glyph (or the end) starts
+                    self.push_back(line);
+                    return Err(err);
+                }
+                _ => {}
+            }
+        }
+
+        let missing = |keyword| self.error(Some("ENDCHAR"), FontErrorKind::MissingKeyword(keyword));
+        if strict {
+            if encoding.is_none() {
+                return Err(missing("ENCODING"));
+            }
+            if bbox.is_none() {
+                return Err(missing("BBX"));
+            }
+            if bitmap.is_none() {
+                return Err(missing("BITMAP"));
+            }
+        }
+        let bbox = bbox.unwrap_or(default_bbox);
+
+        // negative encodings are glyphs without a codepoint
+        let Some(c) = encoding
+            .and_then(|c| u32::try_from(c).ok())
+            .and_then(char::from_u32)
+        else {
+            return Ok(());
+        };
+        let bitmap = bitmap.unwrap_or_else(|| VecBitmap::from_size(bbox.size));
+        let entry = glyphs.push(&bitmap, bbox.origin, width.unwrap_or(bbox.width()));
+        glyphs.insert(c, entry);
+        let name = name.to_owned();
+        info.insert(c, GlyphInfo { name, swidth });
+        Ok(())
+    }
+
+    /// Skips past the `ENDCHAR` of a glyph that failed to parse.
+    fn skip_glyph(&mut self) -> Result<(), FontError> {
+        while let Some(line) = self.next_line()? {
+            match line.split_whitespace().next() {
+                Some("ENDCHAR") => break,
+                Some("STARTCHAR" | "ENDFONT") => {
+                    self.push_back(line);
+                    break;
+                }
+                _ => {}
+            }
+        }
+        Ok(())
+    }
+}
+
+impl BdfFont {
+    /// Parses a BDF font in [`ParseMode::Lenient`] mode.
+    #[inline]
+    pub fn new<R: Read>(reader: &mut BufReader<R>) -> Result<Self, FontError> {
+        Self::with_mode(reader, ParseMode::Lenient)
+    }
+
+    pub fn with_mode<R: Read>(
+        reader: &mut BufReader<R>,
+        mode: ParseMode,
+    ) -> Result<Self, FontError> {
+        let mut parser = Parser {
+            lines: reader.lines(),
+            line: 0,
+            pushed_back: None,
+            // kept in the header, where fonts carry their copyright and license
+            comments: Some(Vec::new()),
+            mode,
+        };
+        let strict = mode == ParseMode::Strict;
+
+        let line = parser.expect_line()?;
+        let mut start = line.split_whitespace();
+        let keyword = start.next().unwrap_or_default();
+        if keyword != "STARTFONT" {
+            return Err(parser.error(Some(keyword), FontErrorKind::NotBdf));
+        }
+        let version = parser.value::<String>(keyword, &mut start);
+        parser.tolerate(version.and_then(|version| match version.as_str() {
+            "2.1" | "2.2" => Ok(()),
+            _ => Err(parser.error(Some(keyword), FontErrorKind::UnsupportedVersion(version))),
+        }))?;
+
+        let mut default_bbox = None;
+        let mut default_width = None;
+        let mut pixel_size = None;
+        let mut ascent = None;
+        let mut descent = None;
+        let mut default_char = None;
+        let mut expected_glyphs = None;
+        let mut header = Vec::new();
+        loop {
+            let line = parser.expect_line()?;
+            header.extend(
+                parser
+                    .comments
+                    .iter_mut()
+                    .flat_map(|comments| comments.drain(..)),
+            );
+            let mut fields = line.split_whitespace();
+            let keyword = fields.next().unwrap_or_default();
+            let result = match keyword {
+                "FONTBOUNDINGBOX" => parser
+                    .bbox(keyword, &mut fields)
+                    .map(|bbox| default_bbox = Some(bbox)),
+                "DWIDTH" => parser
+                    .value(keyword, &mut fields)
+                    .map(|width| default_width = Some(width)),
+                "PIXEL_SIZE" => parser
+                    .value(keyword, &mut fields)
+                    .map(|size| pixel_size = Some(size)),
+                "FONT_ASCENT" => parser
+                    .value(keyword, &mut fields)
+                    .map(|value| ascent = Some(value)),
+                "FONT_DESCENT" => parser
+                    .value(keyword, &mut fields)
+                    .map(|value| descent = Some(value)),
+                "DEFAULT_CHAR" => parser
+                    .value(keyword, &mut fields)
+                    .map(|encoding| default_char = char::from_u32(encoding)),
+                "CHARS" => {
+                    let count = parser.value(keyword, &mut fields);
+                    parser.tolerate(count.map(|count| expected_glyphs = Some(count)))?;
+                    break;
+                }
+                "STARTCHAR" if !strict => {
+                    // no CHARS line, so the glyphs start right away
+                    parser.push_back(line);
+                    break;
+                }
+                "STARTCHAR" | "ENDFONT" => {
+                    Err(parser.error(Some(keyword), FontErrorKind::MissingKeyword("CHARS")))
+                }
+                _ => Ok(()),
+            };
+            parser.tolerate(result)?;
+            header.push(line);
+        }
+        parser.comments = None;
+        if default_bbox.is_none() && strict {
+            return Err(parser.error(None, FontErrorKind::MissingKeyword("FONTBOUNDINGBOX")));
+        }
+        let default_bbox = default_bbox.unwrap_or(Rect::ZERO);
+
+        let mut glyphs = GlyphMap::default();
+        let mut info = FxHashMap::default();
+        let mut found_glyphs = 0;
+        loop {
+            let Some(line) = parser.next_line()? else {
+                if strict {
+                    return Err(parser.error(None, FontErrorKind::MissingKeyword("ENDFONT")));
+                }
+                break;
+            };
+            let mut fields = line.split_whitespace();
+            let keyword = fields.next().unwrap_or_default();
+            match keyword {
+                "STARTCHAR" => {
+                    found_glyphs += 1;
+                    let name = fields.next().unwrap_or_default();
+                    let glyph =
+                        parser.glyph(name, &mut glyphs, &mut info, default_bbox, default_width);
+                    if let Err(err) = glyph {
+                        parser.tolerate(Err(err))?;
+                        parser.skip_glyph()?;
+                    }
+                }
+                "ENDFONT" => {
+                    if let Some(expected) = expected_glyphs {
+                        if expected != found_glyphs {
+                            let count = FontErrorKind::GlyphCount {
+                                expected,
+                                found: found_glyphs,
+                            };
+                            parser.tolerate(Err(parser.error(Some(keyword), count)))?;
+                        }
+                    }
+                    break;
+                }
+                _ => {
+                    let err = parser.error(Some(keyword), FontErrorKind::UnexpectedKeyword);
+                    parser.tolerate(Err(err))?;
+                }
+            }
+        }
+
+        // the bounding box origin is its bottom-left corner relative to the baseline
+        let ascent = ascent.unwrap_or(default_bbox.height() + default_bbox.origin.y);
+        let descent = descent.unwrap_or(-default_bbox.origin.y);
+        Ok(Self {
+            header,
+            glyphs,
+            info,
+            line_height: pixel_size.unwrap_or(default_bbox.height()),
+            ascent,
+            descent,
+            default_char,
+            measure_cache: MeasureCache::default(),
+        })
+    }
+}
+
+impl BdfFont {
+    /// The value of a property or other line in the font's header, without the quotes
+    /// around strings.
+    pub fn property(&self, name: &str) -> Option<&str> {
+        self.header.iter().find_map(|line| {
+            let (keyword, value) = line.trim().split_once(char::is_whitespace)?;
+            let value = value.trim();
+            (keyword == name).then(|| {
+                value
+                    .strip_prefix('"')
+                    .and_then(|value| value.strip_suffix('"'))
+                    .unwrap_or(value)
+            })
+        })
+    }
+
+    /// A copy of the font with only the glyphs needed to draw `texts`, for shipping just
+    /// the chars that are used out of a large font. Properties and metrics are kept.
+    ///
+    /// The default char, space, hyphen and the chars of an ellipsis are kept too, since
+    /// layout may draw them in place of others.
+    pub fn subset<'s, I: IntoIterator<Item = &'s str>>(&self, texts: I) -> Self {
+        let mut chars: Vec<_> = texts.into_iter().flat_map(str::chars).collect();
+        chars.extend(self.default_char);
+        chars.extend([' ', '-', '.', '…']);
+        chars.sort_unstable();
+        chars.dedup();
+
+        let mut glyphs = GlyphMap::default();
+        let mut info = FxHashMap::default();
+        for c in chars {
+            let Some(glyph) = self.glyphs.get(c) else {
+                continue;
+            };
+            let entry = glyphs.push(glyph.bitmap(), glyph.offset(), glyph.width());
+            glyphs.insert(c, entry);
+            if let Some(glyph_info) = self.info.get(&c) {
+                info.insert(c, glyph_info.clone());
+            }
+        }
+        Self {
+            line_height: self.line_height,
+            ascent: self.ascent,
+            descent: self.descent,
+            default_char: self.default_char,
+            header: self.header.clone(),
+            glyphs,
+            info,
+            measure_cache: MeasureCache::default(),
+        }
+    }
+
+    /// Writes the font out as BDF 2.1, glyphs in order of their codepoints.
+    pub fn write<W: Write>(&self, writer: &mut W) -> io::Result<()> {
+        let mut chars: Vec<_> = self.glyphs.chars().collect();
+        chars.sort_unstable();
+
+        writeln!(writer, "STARTFONT 2.1")?;
+        for line in &self.header {
+            writeln!(writer, "{line}")?;
+        }
+        writeln!(writer, "CHARS {}", chars.len())?;
+        for c in chars {
+            let Some(glyph) = self.glyphs.get(c) else {
+                continue;
+            };
+            let info = self.info.get(&c);
+            let code = c as u32;
+            match info {
+                Some(info) => writeln!(writer, "STARTCHAR {}", info.name)?,
+                None => writeln!(writer, "STARTCHAR uni{code:04X}")?,
+            }
+            writeln!(writer, "ENCODING {code}")?;
+            // without the original, assume points are pixels
+            let swidth = info
+                .and_then(|info| info.swidth)
+                .unwrap_or_else(|| Point::new(glyph.width() * 1000 / self.line_height.max(1), 0));
+            writeln!(writer, "SWIDTH {} {}", swidth.x, swidth.y)?;
+            writeln!(writer, "DWIDTH {} 0", glyph.width())?;
+            let (size, offset) = (glyph.size(), glyph.offset());
+            writeln!(
+                writer,
+                "BBX {} {} {} {}",
+                size.width, size.height, offset.x, offset.y
+            )?;
+            writeln!(writer, "BITMAP")?;
+            let bitmap = glyph.bitmap();
+            for y in 0..size.height {
+                let row = bitmap.row(y);
+                // rows of zero-width glyphs still need a byte to not be read as missing
+                if row.is_empty() {
+                    write!(writer, "00")?;
+                }
+                for byte in row {
+                    write!(writer, "{byte:02X}")?;
+                }
+                writeln!(writer)?;
+            }
+            writeln!(writer, "ENDCHAR")?;
+        }
+        writeln!(writer, "ENDFONT")
+    }
+}
+
+impl Font for BdfFont {
+    #[inline]
+    fn line_height(&self) -> Scalar {
+        self.line_height
+    }
+
+    #[inline]
+    fn ascent(&self) -> Scalar {
+        self.ascent
+    }
+
+    #[inline]
+    fn descent(&self) -> Scalar {
+        self.descent
+    }
+
+    #[inline]
+    fn glyph(&self, c: char) -> Option<Glyph<'_>> {
+        self.glyphs.get(c)
+    }
+
+    #[inline]
+    fn default_char(&self) -> Option<char> {
+        self.default_char
+    }
+
+    #[inline]
+    fn measure_cache(&self) -> Option<&MeasureCache> {
+        Some(&self.measure_cache)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const FONT: &str = "\
+STARTFONT 2.1
+COMMENT Copyright (c) the test font's authors
+COMMENT Licensed for testing only
+FONT -test-fixed-medium-r-normal--8-80-75-75-c-60-iso10646-1
+SIZE 8 75 75
+FONTBOUNDINGBOX 6 8 0 -2
+STARTPROPERTIES 3
+FONT_ASCENT 6
+FONT_DESCENT 2
+DEFAULT_CHAR 63
+ENDPROPERTIES
+CHARS 4
+STARTCHAR space
+ENCODING 32
+SWIDTH 750 0
+DWIDTH 6 0
+BBX 0 8 0 -2
+BITMAP
+00
+00
+00
+00
+00
+00
+00
+00
+ENDCHAR
+STARTCHAR question
+ENCODING 63
+SWIDTH 750 0
+DWIDTH 6 0
+BBX 4 3 1 3
+BITMAP
+60
+90
+20
+ENDCHAR
+STARTCHAR A
+ENCODING 65
+SWIDTH 750 0
+DWIDTH 6 0
+BBX 5 6 0 0
+BITMAP
+70
+88
+88
+F8
+88
+88
+ENDCHAR
+STARTCHAR B
+ENCODING 66
+SWIDTH 750 0
+DWIDTH 6 0
+BBX 5 6 0 0
+BITMAP
+F0
+88
+F0
+88
+88
+F0
+ENDCHAR
+ENDFONT
+";
+
+    fn parse(source: &str, mode: ParseMode) -> Result<BdfFont, FontError> {
+        BdfFont::with_mode(&mut BufReader::new(source.as_bytes()), mode)
+    }
+
+    /// Parses `source` strictly, expecting an error, and leniently, expecting a font.
+    fn strict_error(source: &str) -> (FontError, BdfFont) {
+        let err = parse(source, ParseMode::Strict).err().unwrap();
+        (err, parse(source, ParseMode::Lenient).unwrap())
+    }
+
+    fn write(font: &BdfFont) -> String {
+        let mut out = Vec::new();
+        font.write(&mut out).unwrap();
+        String::from_utf8(out).unwrap()
+    }
+
+    fn assert_same_glyphs(a: &BdfFont, b: &BdfFont) {
+        let mut chars: Vec<_> = a.glyphs.chars().collect();
+        chars.sort_unstable();
+        let mut other: Vec<_> = b.glyphs.chars().collect();
+        other.sort_unstable();
+        assert_eq!(chars, other);
+        for c in chars {
+            assert_eq!(a.glyph(c), b.glyph(c), "{c:?}");
+        }
+    }
+
+    #[test]
+    fn write_round_trips_strictly() {
+        let font = parse(FONT, ParseMode::Strict).unwrap();
+        let written = write(&font);
+        let reparsed = parse(&written, ParseMode::Strict).unwrap();
+        assert_same_glyphs(&font, &reparsed);
+        assert_eq!(reparsed.line_height(), font.line_height());
+        assert_eq!(reparsed.ascent(), font.ascent());
+        assert_eq!(reparsed.descent(), font.descent());
+        assert_eq!(reparsed.default_char(), Some('?'));
+        assert_eq!(write(&reparsed), written);
+    }
+
+    #[test]
+    fn write_pads_rows_of_zero_width_glyphs() {
+        let font = parse(FONT, ParseMode::Strict).unwrap();
+        let written = write(&font);
+        let space = written.split("STARTCHAR space\n").nth(1).unwrap();
+        assert!(space.starts_with("ENCODING 32\nSWIDTH 750 0\nDWIDTH 6 0\nBBX 0 8 0 -2\n"));
+        assert_eq!(space.lines().skip(5).take(8).collect::<Vec<_>>(), ["00"; 8]);
+    }
+
+    #[test]
+    fn subset_keeps_properties() {
+        let font = parse(FONT, ParseMode::Strict).unwrap();
+        let subset = font.subset(["A"]);
+        assert_eq!(subset.property("FONT_ASCENT"), Some("6"));
+        assert_eq!(subset.property("DEFAULT_CHAR"), Some("63"));
+        assert_eq!(subset.property("SIZE"), Some("8 75 75"));
+        assert!(subset.glyph('A').is_some());
+        assert!(subset.glyph('?').is_some());
+        assert!(subset.glyph(' ').is_some());
+        assert!(subset.glyph('B').is_none());
+
+        let comments: Vec<_> = subset
+            .header
+            .iter()
+            .filter(|line| line.starts_with("COMMENT"))
+            .collect();
+        assert_eq!(
+            comments,
+            [
+                "COMMENT Copyright (c) the test font's authors",
+                "COMMENT Licensed for testing only"
+            ]
+        );
+
+        let reparsed = parse(&write(&subset), ParseMode::Strict).unwrap();
+        assert_same_glyphs(&subset, &reparsed);
+        assert_eq!(reparsed.property("FONT"), font.property("FONT"));
+        assert!(write(&reparsed).starts_with(
+            "STARTFONT 2.1\nCOMMENT Copyright (c) the test font's authors\nCOMMENT Licensed"
+        ));
+        assert_eq!(reparsed.default_char(), Some('?'));
+    }
+
+    #[test]
+    fn malformed_glyphs_are_skipped_leniently() {
+        let (err, font) = strict_error(&FONT.replace("ENCODING 65", "ENCODING A"));
+        assert_eq!((err.line(), err.keyword()), (39, Some("ENCODING")));
+        assert!(matches!(err.kind(), FontErrorKind::InvalidValue(value) if value == "A"));
+        assert!(font.glyph('A').is_none());
+        assert!(font.glyph('B').is_some());
+        assert!(font.glyph('?').is_some());
+    }
+
+    #[test]
+    fn huge_bounding_boxes_are_skipped_leniently() {
+        let source = FONT.replacen("BBX 5 6 0 0", "BBX 2000000000 2000000000 0 0", 1);
+        let (err, font) = strict_error(&source);
+        assert_eq!((err.line(), err.keyword()), (42, Some("BBX")));
+        assert!(matches!(err.kind(), FontErrorKind::InvalidValue(_)));
+        assert!(font.glyph('A').is_none());
+        assert!(font.glyph('B').is_some());
+    }
+
+    #[test]
+    fn short_bitmaps_are_padded_leniently() {
+        let source = FONT.replace("F8\n88\n88\nENDCHAR\nSTARTCHAR B", "ENDCHAR\nSTARTCHAR B");
+        let (err, font) = strict_error(&source);
+        assert_eq!(err.line(), 47);
+        assert!(matches!(err.kind(), FontErrorKind::InvalidBitmap));
+        let glyph = font.glyph('A').unwrap();
+        let rows: Vec<_> = (0..6).map(|y| glyph.bitmap().row(y)[0]).collect();
+        assert_eq!(rows, [0x70, 0x88, 0x88, 0, 0, 0]);
+        assert!(font.glyph('B').is_some());
+    }
+
+    #[test]
+    fn zero_width_glyphs_without_rows() {
+        let rows = "00\n".repeat(8);
+        let source = FONT.replace(&format!("BITMAP\n{rows}ENDCHAR"), "BITMAP\nENDCHAR");
+        let (err, font) = strict_error(&source);
+        assert_eq!(err.line(), 19);
+        assert!(matches!(err.kind(), FontErrorKind::InvalidBitmap));
+        // ENDCHAR ends the glyph instead of being read as a row
+        assert_eq!(font.glyph(' ').unwrap().size(), Size::new(0, 8));
+        assert!(font.glyph('?').is_some());
+    }
+
+    #[test]
+    fn missing_endchar_skips_to_the_next_glyph() {
+        let source = FONT.replace("ENDCHAR\nSTARTCHAR B", "STARTCHAR B");
+        let (err, font) = strict_error(&source);
+        assert_eq!(err.keyword(), Some("STARTCHAR"));
+        assert!(matches!(
+            err.kind(),
+            FontErrorKind::MissingKeyword("ENDCHAR")
+        ));
+        assert!(font.glyph('A').is_none());
+        assert!(font.glyph('B').is_some());
+    }
+
+    #[test]
+    fn header_deviations_are_tolerated_leniently() {
+        let (err, _) = strict_error(&FONT.replace("STARTFONT 2.1", "STARTFONT 3.0"));
+        assert!(
+            matches!(err.kind(), FontErrorKind::UnsupportedVersion(version) if version == "3.0")
+        );
+
+        let (err, font) = strict_error(&FONT.replace("FONTBOUNDINGBOX 6 8 0 -2\n", ""));
+        assert!(matches!(
+            err.kind(),
+            FontErrorKind::MissingKeyword("FONTBOUNDINGBOX")
+        ));
+        assert_eq!((font.ascent(), font.descent()), (6, 2));
+
+        let (err, font) = strict_error(&FONT.replace("CHARS 4", "CHARS 5"));
+        assert!(matches!(
+            err.kind(),
+            FontErrorKind::GlyphCount {
+                expected: 5,
+                found: 4
+            }
+        ));
+        assert!(font.glyph('B').is_some());
+    }
+
+    #[test]
+    fn truncated_and_foreign_files_fail_in_both_modes() {
+        let truncated = &FONT[..FONT.find("ENCODING 66").unwrap()];
+        for mode in [ParseMode::Strict, ParseMode::Lenient] {
+            let err = parse(truncated, mode).err().unwrap();
+            assert!(matches!(err.kind(), FontErrorKind::UnexpectedEof));
+            let err = parse("FONT x\n", mode).err().unwrap();
+            assert!(matches!(err.kind(), FontErrorKind::NotBdf));
+        }
+    }
+}
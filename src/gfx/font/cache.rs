@@ -0,0 +1,248 @@
+use std::{
+    cell::{Cell, RefCell},
+    hash::{Hash, Hasher},
+};
+
+use fxhash::{FxHashMap, FxHasher};
+
+use crate::gfx::{Size, TextOptions};
+
+struct Entry {
+    text: String,
+    limits: Size,
+    options: TextOptions,
+    size: Size,
+    last_used: u64,
+}
+
+/// A bounded least-recently-used cache of text measurements, keyed by text, limits and
+/// options.
+///
+/// Fonts own one of these and consult it from [`Font::measure`](crate::gfx::Font::measure),
+/// since containers tend to measure the same children many times per frame.
+pub struct MeasureCache {
+    entries: RefCell<FxHashMap<u64, Entry>>,
+    capacity: Cell<usize>,
+    clock: Cell<u64>,
+    hits: Cell<u64>,
+    misses: Cell<u64>,
+}
+
+impl Default for MeasureCache {
+    #[inline]
+    fn default() -> Self {
+        Self::new(Self::DEFAULT_CAPACITY)
+    }
+}
+
+impl MeasureCache {
+    pub const DEFAULT_CAPACITY: usize = 256;
+
+    /// Creates a cache holding at most `capacity` measurements. A capacity of zero
+    /// disables caching.
+    #[inline]
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            entries: RefCell::new(FxHashMap::default()),
+            capacity: Cell::new(capacity),
+            clock: Cell::new(0),
+            hits: Cell::new(0),
+            misses: Cell::new(0),
+        }
+    }
+
+    #[inline]
+    pub fn capacity(&self) -> usize {
+        self.capacity.get()
+    }
+
+    /// Changes the capacity, evicting the least recently used measurements if the
+    /// cache is now over it.
+    pub fn set_capacity(&self, capacity: usize) {
+        self.capacity.set(capacity);
+        let mut entries = self.entries.borrow_mut();
+        while entries.len() > capacity {
+            Self::evict(&mut entries);
+        }
+    }
+
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.entries.borrow().len()
+    }
+
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.entries.borrow().is_empty()
+    }
+
+    /// Forgets every measurement. The hit and miss counters are left alone.
+    #[inline]
+    pub fn clear(&self) {
+        self.entries.borrow_mut().clear();
+    }
+
+    #[inline]
+    pub fn hits(&self) -> u64 {
+        self.hits.get()
+    }
+
+    #[inline]
+    pub fn misses(&self) -> u64 {
+        self.misses.get()
+    }
+
+    #[inline]
+    pub fn reset_stats(&self) {
+        self.hits.set(0);
+        self.misses.set(0);
+    }
+
+    /// Returns the cached measurement of `text` within `limits`, or calls `measure` and
+    /// remembers its result.
+    pub fn get_or_measure<F>(
+        &self,
+        limits: Size,
+        text: &str,
+        options: &TextOptions,
+        measure: F,
+    ) -> Size
+    where
+        F: FnOnce() -> Size,
+    {
+        let capacity = self.capacity.get();
+        if capacity == 0 {
+            self.misses.set(self.misses.get() + 1);
+            return measure();
+        }
+
+        let clock = self.clock.get() + 1;
+        self.clock.set(clock);
+
+        // hash the key ourselves so that lookups don't have to allocate a String
+        let mut hasher = FxHasher::default();
+        text.hash(&mut hasher);
+        limits.hash(&mut hasher);
+        options.hash(&mut hasher);
+        let key = hasher.finish();
+
+        if let Some(entry) = self.entries.borrow_mut().get_mut(&key) {
+            if entry.text == text && entry.limits == limits && entry.options == *options {
+                entry.last_used = clock;
+                self.hits.set(self.hits.get() + 1);
+                return entry.size;
+            }
+        }
+        self.misses.set(self.misses.get() + 1);
+
+        // measure without holding the borrow, in case it re-enters the cache
+        let size = measure();
+        let mut entries = self.entries.borrow_mut();
+        if !entries.contains_key(&key) && entries.len() >= capacity {
+            Self::evict(&mut entries);
+        }
+        entries.insert(
+            key,
+            Entry {
+                text: text.to_owned(),
+                limits,
+                options: options.clone(),
+                size,
+                last_used: clock,
+            },
+        );
+        size
+    }
+
+    fn evict(entries: &mut FxHashMap<u64, Entry>) {
+        let oldest = entries
+            .iter()
+            .min_by_key(|(_, entry)| entry.last_used)
+            .map(|(&key, _)| key);
+        if let Some(key) = oldest {
+            entries.remove(&key);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::gfx::Scalar;
+
+    /// Looks `text` up, returning whether it had to be measured.
+    fn measure(cache: &MeasureCache, text: &str, limits: Size, options: &TextOptions) -> bool {
+        let mut measured = false;
+        let size = cache.get_or_measure(limits, text, options, || {
+            measured = true;
+            Size::new(text.len() as Scalar, 1)
+        });
+        assert_eq!(size, Size::new(text.len() as Scalar, 1));
+        measured
+    }
+
+    #[test]
+    fn evicts_least_recently_used() {
+        let cache = MeasureCache::new(2);
+        let (limits, options) = (Size::new(100, 100), TextOptions::default());
+        assert!(measure(&cache, "a", limits, &options));
+        assert!(measure(&cache, "bb", limits, &options));
+        assert!(!measure(&cache, "a", limits, &options));
+        // "bb" is now the least recently used
+        assert!(measure(&cache, "ccc", limits, &options));
+        assert_eq!(cache.len(), 2);
+        assert!(!measure(&cache, "a", limits, &options));
+        assert!(!measure(&cache, "ccc", limits, &options));
+        assert!(measure(&cache, "bb", limits, &options));
+        assert_eq!((cache.hits(), cache.misses()), (3, 4));
+    }
+
+    #[test]
+    fn keys_include_limits_and_options() {
+        let cache = MeasureCache::default();
+        let options = TextOptions::default();
+        let wrapped = TextOptions {
+            max_lines: Some(1),
+            ..TextOptions::default()
+        };
+        assert!(measure(&cache, "a", Size::new(10, 10), &options));
+        assert!(measure(&cache, "a", Size::new(20, 10), &options));
+        assert!(measure(&cache, "a", Size::new(10, 10), &wrapped));
+        assert!(!measure(&cache, "a", Size::new(10, 10), &options));
+        assert_eq!(cache.len(), 3);
+    }
+
+    #[test]
+    fn shrinking_keeps_the_most_recent() {
+        let cache = MeasureCache::new(3);
+        let (limits, options) = (Size::new(100, 100), TextOptions::default());
+        for text in ["a", "bb", "ccc"] {
+            measure(&cache, text, limits, &options);
+        }
+        measure(&cache, "a", limits, &options);
+        cache.set_capacity(2);
+        assert_eq!(cache.len(), 2);
+        assert!(!measure(&cache, "a", limits, &options));
+        assert!(!measure(&cache, "ccc", limits, &options));
+        assert!(measure(&cache, "bb", limits, &options));
+    }
+
+    #[test]
+    fn zero_capacity_and_clearing() {
+        let cache = MeasureCache::new(0);
+        let (limits, options) = (Size::new(100, 100), TextOptions::default());
+        assert!(measure(&cache, "a", limits, &options));
+        assert!(measure(&cache, "a", limits, &options));
+        assert!(cache.is_empty());
+
+        cache.set_capacity(1);
+        measure(&cache, "a", limits, &options);
+        assert!(!measure(&cache, "a", limits, &options));
+        cache.clear();
+        assert!(cache.is_empty());
+        assert!(measure(&cache, "a", limits, &options));
+        assert_eq!((cache.hits(), cache.misses()), (1, 4));
+        cache.reset_stats();
+        assert_eq!((cache.hits(), cache.misses()), (0, 0));
+    }
+}
@@ -0,0 +1,116 @@
+use std::{
+    error::Error,
+    fmt::{self, Display, Formatter},
+    io::{self, ErrorKind},
+};
+
+#[derive(Debug)]
+pub enum FontErrorKind {
+    Io(io::Error),
+    UnexpectedEof,
+    /// The file doesn't start with `STARTFONT`.
+    NotBdf,
+    UnsupportedVersion(String),
+    MissingValue,
+    InvalidValue(String),
+    MissingKeyword(&'static str),
+    UnexpectedKeyword,
+    InvalidBitmap,
+    GlyphCount {
+        expected: usize,
+        found: usize,
+    },
+}
+
+impl Display for FontErrorKind {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Io(err) => write!(f, "{err}"),
+            Self::UnexpectedEof => write!(f, "unexpected end of file"),
+            Self::NotBdf => write!(f, "not a BDF font"),
+            Self::UnsupportedVersion(version) => write!(f, "unsupported version {version:?}"),
+            Self::MissingValue => write!(f, "missing value"),
+            Self::InvalidValue(value) => write!(f, "invalid value {value:?}"),
+            Self::MissingKeyword(keyword) => write!(f, "missing {keyword}"),
+            Self::UnexpectedKeyword => write!(f, "unexpected keyword"),
+            Self::InvalidBitmap => write!(f, "malformed bitmap row"),
+            Self::GlyphCount { expected, found } => {
+                write!(f, "expected {expected} glyphs but found {found}")
+            }
+        }
+    }
+}
+
+/// An error encountered while parsing a font, along with where it happened.
+#[derive(Debug)]
+pub struct FontError {
+    line: usize,
+    keyword: Option<String>,
+    kind: FontErrorKind,
+}
+
+impl FontError {
+    #[inline]
+    pub(crate) fn new(line: usize, keyword: Option<&str>, kind: FontErrorKind) -> Self {
+        Self {
+            line,
+            keyword: keyword.map(str::to_owned),
+            kind,
+        }
+    }
+
+    /// The 1-based line the error was found on.
+    #[inline]
+    pub fn line(&self) -> usize {
+        self.line
+    }
+
+    /// The keyword of the offending line, if it had one.
+    #[inline]
+    pub fn keyword(&self) -> Option<&str> {
+        self.keyword.as_deref()
+    }
+
+    #[inline]
+    pub fn kind(&self) -> &FontErrorKind {
+        &self.kind
+    }
+
+    /// Whether parsing can skip past the error instead of giving up on the whole font.
+    #[inline]
+    pub(crate) fn is_recoverable(&self) -> bool {
+        !matches!(
+            self.kind,
+            FontErrorKind::Io(_) | FontErrorKind::UnexpectedEof
+        )
+    }
+}
+
+impl Display for FontError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "line {}: ", self.line)?;
+        if let Some(keyword) = &self.keyword {
+            write!(f, "{keyword}: ")?;
+        }
+        write!(f, "{}", self.kind)
+    }
+}
+
+impl Error for FontError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match &self.kind {
+            FontErrorKind::Io(err) => Some(err),
+            _ => None,
+        }
+    }
+}
+
+impl From<FontError> for io::Error {
+    fn from(err: FontError) -> Self {
+        match err.kind {
+            FontErrorKind::Io(err) => err,
+            FontErrorKind::UnexpectedEof => io::Error::new(ErrorKind::UnexpectedEof, err),
+            _ => io::Error::new(ErrorKind::InvalidData, err),
+        }
+    }
+}
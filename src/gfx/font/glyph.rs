@@ -0,0 +1,136 @@
+use std::borrow::Borrow;
+
+use fxhash::FxHashMap;
+
+use crate::gfx::{Bitmap, Point, Scalar, Size};
+
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Hash)]
+pub struct Glyph<'a> {
+    bitmap: Bitmap<&'a [u8]>,
+    offset: Point,
+    width: Scalar,
+    scale: Scalar,
+}
+
+impl<'a> Glyph<'a> {
+    #[inline]
+    pub const fn new(bitmap: Bitmap<&'a [u8]>, offset: Point, width: Scalar) -> Self {
+        Self {
+            bitmap,
+            offset,
+            width,
+            scale: 1,
+        }
+    }
+
+    /// Blows the glyph up by a whole number of pixels per pixel of its bitmap.
+    #[inline]
+    pub const fn scaled(self, scale: Scalar) -> Self {
+        Self {
+            scale: self.scale * scale,
+            ..self
+        }
+    }
+
+    #[inline]
+    pub const fn bitmap(&self) -> &Bitmap<&'a [u8]> {
+        &self.bitmap
+    }
+
+    /// How many pixels wide and tall each pixel of the bitmap is drawn.
+    #[inline]
+    pub const fn scale(&self) -> Scalar {
+        self.scale
+    }
+
+    /// The size of the glyph once scaled.
+    #[inline]
+    pub const fn size(&self) -> Size {
+        let size = self.bitmap.size();
+        Size::new(size.width * self.scale, size.height * self.scale)
+    }
+
+    /// The position of the bottom-left corner of the bitmap relative to the origin on
+    /// the baseline, with positive y pointing up.
+    #[inline]
+    pub const fn offset(&self) -> Point {
+        Point::new(self.offset.x * self.scale, self.offset.y * self.scale)
+    }
+
+    /// How far the cursor advances after this glyph.
+    #[inline]
+    pub const fn width(&self) -> Scalar {
+        self.width * self.scale
+    }
+}
+
+#[derive(Copy, Clone, Debug)]
+pub(crate) struct GlyphEntry {
+    start: usize,
+    size: Size,
+    offset: Point,
+    width: Scalar,
+}
+
+/// Glyph bitmaps for a whole font packed into one buffer.
+///
+/// Every row is padded only to a whole byte, and chars that share a glyph share its bits.
+#[derive(Debug, Default)]
+pub(crate) struct GlyphMap {
+    bits: Vec<u8>,
+    entries: FxHashMap<char, GlyphEntry>,
+}
+
+impl GlyphMap {
+    /// Copies a bitmap into the map without assigning it to any char yet.
+    pub(crate) fn push<S: Borrow<[u8]>>(
+        &mut self,
+        bitmap: &Bitmap<S>,
+        offset: Point,
+        width: Scalar,
+    ) -> GlyphEntry {
+        let start = self.bits.len();
+        let size = bitmap.size();
+        let stride = Bitmap::<S>::min_stride(size.width);
+        for y in 0..size.height {
+            self.bits.extend_from_slice(&bitmap.row(y)[..stride]);
+        }
+        GlyphEntry {
+            start,
+            size,
+            offset,
+            width,
+        }
+    }
+
+    #[inline]
+    pub(crate) fn insert(&mut self, c: char, entry: GlyphEntry) {
+        self.entries.insert(c, entry);
+    }
+
+    /// Every char with a glyph, in no particular order.
+    #[inline]
+    pub(crate) fn chars(&self) -> impl Iterator<Item = char> + '_ {
+        self.entries.keys().copied()
+    }
+
+    #[inline]
+    pub(crate) fn get(&self, c: char) -> Option<Glyph<'_>> {
+        self.entries.get(&c).map(|entry| self.glyph(entry))
+    }
+
+    #[inline]
+    pub(crate) fn glyph(&self, entry: &GlyphEntry) -> Glyph<'_> {
+        let stride = Bitmap::<&[u8]>::min_stride(entry.size.width);
+        let len = stride * entry.size.height as usize;
+        Glyph::new(
+            Bitmap::new(
+                &self.bits[entry.start..entry.start + len],
+                stride,
+                entry.size,
+            ),
+            entry.offset,
+            entry.width,
+        )
+    }
+}
@@ -0,0 +1,122 @@
+use crate::gfx::{Bitmap, BlendMode, Color, Font, Glyph, Point, Rect, Scalar, Size, WriteSurface};
+
+/// 3x5 bitmaps of the hex digits, one byte per row.
+#[rustfmt::skip]
+const DIGITS: [u8; 80] = [
+    0xE0, 0xA0, 0xA0, 0xA0, 0xE0, // 0
+    0x40, 0xC0, 0x40, 0x40, 0xE0, // 1
+    0xE0, 0x20, 0xE0, 0x80, 0xE0, // 2
+    0xE0, 0x20, 0x60, 0x20, 0xE0, // 3
+    0xA0, 0xA0, 0xE0, 0x20, 0x20, // 4
+    0xE0, 0x80, 0xE0, 0x20, 0xE0, // 5
+    0xE0, 0x80, 0xE0, 0xA0, 0xE0, // 6
+    0xE0, 0x20, 0x20, 0x40, 0x40, // 7
+    0xE0, 0xA0, 0xE0, 0xA0, 0xE0, // 8
+    0xE0, 0xA0, 0xE0, 0x20, 0xE0, // 9
+    0x40, 0xA0, 0xE0, 0xA0, 0xA0, // A
+    0xC0, 0xA0, 0xC0, 0xA0, 0xC0, // B
+    0x60, 0x80, 0x80, 0x80, 0x60, // C
+    0xC0, 0xA0, 0xA0, 0xA0, 0xC0, // D
+    0xE0, 0x80, 0xC0, 0x80, 0xE0, // E
+    0xE0, 0x80, 0xC0, 0x80, 0x80, // F
+];
+
+const DIGIT_SIZE: Size = Size::new(3, 5);
+
+/// A box with a char's codepoint written inside, for chars that a font can't draw.
+///
+/// The digits go in two rows if the font is tall enough, one row if not, and are left
+/// out of an empty box if the font is too short for even that.
+pub(crate) struct HexBox {
+    c: char,
+    /// The box, relative to the start of its glyph on the baseline.
+    rect: Rect,
+    digits: usize,
+    columns: usize,
+}
+
+impl HexBox {
+    pub(crate) fn new<F: Font + ?Sized>(font: &F, c: char) -> Self {
+        let height = font.ascent() + font.descent();
+        let digits = match c as u32 {
+            0..=0xFF => 2,
+            0x100..=0xFFFF => 4,
+            _ => 6,
+        };
+        // a 1px border and 1px of padding around digits with 1px between them
+        let (digits, rows) = if digits > 2 && height >= 2 * (DIGIT_SIZE.height + 1) + 3 {
+            (digits, 2)
+        } else if height >= DIGIT_SIZE.height + 4 {
+            (digits, 1)
+        } else {
+            (0, 1)
+        };
+        let columns = digits / rows;
+        let size = if digits == 0 {
+            Size::new((height / 2).max(3), height.max(3))
+        } else {
+            Size::new(
+                columns as Scalar * (DIGIT_SIZE.width + 1) + 3,
+                rows as Scalar * (DIGIT_SIZE.height + 1) + 3,
+            )
+        };
+        // centred on the font's ascent and descent
+        let top = -font.ascent() + (height - size.height) / 2;
+        Self {
+            c,
+            rect: Rect::new(Point::new(0, top), size),
+            digits,
+            columns,
+        }
+    }
+
+    /// A blank glyph as wide as the box, to lay out in place of the char.
+    #[inline]
+    pub(crate) fn glyph(&self) -> Glyph<'static> {
+        Glyph::new(
+            Bitmap::new(&[], 0, Size::ZERO),
+            Point::ZERO,
+            self.rect.width() + 1,
+        )
+    }
+
+    /// Draws the box for a glyph that starts at `origin` on the baseline.
+    pub(crate) fn draw(
+        &self,
+        surface: &mut dyn WriteSurface,
+        bounds: Rect,
+        origin: Point,
+        color: Color,
+        blend: BlendMode,
+    ) {
+        let rect = Rect::new(origin + self.rect.origin, self.rect.size);
+        let Size { width, height } = rect.size;
+        let edges = [
+            Rect::new(rect.origin, Size::new(width, 1)),
+            Rect::new(rect.origin + Point::new(0, height - 1), Size::new(width, 1)),
+            Rect::new(rect.origin, Size::new(1, height)),
+            Rect::new(rect.origin + Point::new(width - 1, 0), Size::new(1, height)),
+        ];
+        for edge in edges {
+            let edge = edge.intersect(bounds);
+            if !edge.is_zero() {
+                surface.fill(edge, color, blend);
+            }
+        }
+
+        let code = self.c as u32;
+        for i in 0..self.digits {
+            let digit = (code >> (4 * (self.digits - 1 - i))) as usize & 0xF;
+            let mask = Bitmap::new(&DIGITS[digit * 5..digit * 5 + 5], 1, DIGIT_SIZE);
+            let (row, column) = ((i / self.columns) as Scalar, (i % self.columns) as Scalar);
+            let to = rect.origin
+                + Point::new(
+                    2 + column * (DIGIT_SIZE.width + 1),
+                    2 + row * (DIGIT_SIZE.height + 1),
+                );
+            let visible = Rect::new(to, DIGIT_SIZE).intersect(bounds);
+            let from = Rect::new(visible.origin - to, visible.size);
+            surface.blit_mask(from, to, &mask, color, blend);
+        }
+    }
+}
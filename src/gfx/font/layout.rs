@@ -0,0 +1,636 @@
+use std::{borrow::Cow, mem, ops::Range};
+
+use crate::gfx::{
+    Font, Glyph, HexBox, MissingGlyph, Point, Rect, Scalar, Size, TabStops, TextAlign, TextOptions,
+    TextOverflow, TextStyle, TextWrap, VerticalAlign, WhiteSpace,
+};
+
+/// A piece of text set in a single font.
+pub(crate) struct Run<'a, F: ?Sized> {
+    pub(crate) font: &'a F,
+    pub(crate) text: &'a str,
+    pub(crate) style: TextStyle,
+}
+
+impl<'a, F: Font + ?Sized> Run<'a, F> {
+    /// How far the cursor moves past `glyph`, including any faux bold.
+    #[inline]
+    fn advance(&self, glyph: &Glyph) -> Scalar {
+        glyph.width() + self.style.bold as Scalar * glyph.scale()
+    }
+}
+
+/// Where [`layout`] put a glyph.
+pub(crate) struct GlyphBox<'a> {
+    /// The index of the run the glyph came from.
+    pub(crate) run: usize,
+    pub(crate) glyph: Glyph<'a>,
+    /// Where the top-left corner of the bitmap goes.
+    pub(crate) point: Point,
+    /// The advance of the glyph across the whole height of its line.
+    pub(crate) cell: Rect,
+    /// The y of the baseline of the line.
+    pub(crate) baseline: Scalar,
+    /// A char with no glyph, to be drawn as a [`HexBox`].
+    pub(crate) missing: Option<char>,
+    /// The offset in the joined text of what the glyph stands for.
+    pub(crate) index: usize,
+    /// The length of what the glyph stands for, which is zero for an ellipsis.
+    pub(crate) len: usize,
+}
+
+/// A glyph placed `x` pixels from the start of its line.
+#[derive(Copy, Clone)]
+struct Placed<'a> {
+    glyph: Glyph<'a>,
+    x: Scalar,
+    advance: Scalar,
+    run: usize,
+    space: bool,
+    missing: Option<char>,
+    index: usize,
+    len: usize,
+}
+
+#[derive(Default)]
+struct Line<'a> {
+    glyphs: Vec<Placed<'a>>,
+    /// Width up to the end of the last glyph that isn't a space.
+    width: Scalar,
+    /// Whether the line ends its paragraph, rather than having been wrapped.
+    end: bool,
+    /// The run the line starts in, which sizes the line if it has no glyphs.
+    run: usize,
+    /// The offset in the joined text where the line starts.
+    start: usize,
+}
+
+/// The vertical extent of a line, and the room its styles need on either side.
+#[derive(Copy, Clone, Default)]
+struct Metrics {
+    ascent: Scalar,
+    descent: Scalar,
+    left: Scalar,
+    right: Scalar,
+}
+
+impl Metrics {
+    #[inline]
+    fn height(&self) -> Scalar {
+        self.ascent + self.descent
+    }
+}
+
+impl<'a> Line<'a> {
+    /// The metrics of the tallest fonts and widest styles on the line.
+    fn metrics<F: Font + ?Sized>(&self, runs: &[Run<F>]) -> Metrics {
+        let mut metrics = Metrics::default();
+        let used = self
+            .glyphs
+            .iter()
+            .map(|placed| (placed.run, placed.glyph.scale()));
+        for (run, scale) in used.chain([(self.run, 1)]) {
+            let Run { font, style, .. } = &runs[run];
+            let (left, top, right, bottom) = style.padding(scale);
+            let baseline = font.baseline();
+            metrics.ascent = metrics.ascent.max(baseline + top);
+            metrics.descent = metrics.descent.max(font.line_height() - baseline + bottom);
+            metrics.left = metrics.left.max(left);
+            metrics.right = metrics.right.max(right);
+        }
+        metrics
+    }
+}
+
+/// Lays out `runs` within `bounds` as one piece of text, calling `op` with each glyph
+/// and where it goes. Returns the size of the text.
+#[inline]
+pub(crate) fn layout<'a, F: Font + ?Sized, O>(
+    runs: &[Run<'a, F>],
+    bounds: Rect,
+    options: &TextOptions,
+    op: O,
+) -> Size
+where
+    O: FnMut(GlyphBox<'a>),
+{
+    layout_lines(runs, bounds, options, op, |_, _, _| {})
+}
+
+/// Like [`layout`], but also calls `line_op` after the glyphs of each line with the
+/// range of the joined text from its start to the start of the next line, the box
+/// around it and its baseline.
+pub(crate) fn layout_lines<'a, F: Font + ?Sized, O, L>(
+    runs: &[Run<'a, F>],
+    bounds: Rect,
+    options: &TextOptions,
+    mut op: O,
+    mut line_op: L,
+) -> Size
+where
+    O: FnMut(GlyphBox<'a>),
+    L: FnMut(Range<usize>, Rect, Scalar),
+{
+    if runs.is_empty() {
+        return Size::ZERO;
+    }
+    let mut lines = break_lines(runs, bounds, options);
+    let metrics: Vec<_> = lines.iter().map(|line| line.metrics(runs)).collect();
+    // a line runs up to where the next one starts, even if that one gets dropped
+    let len = runs.iter().map(|run| run.text.len()).sum();
+    let ends: Vec<_> = lines
+        .iter()
+        .skip(1)
+        .map(|line| line.start)
+        .chain([len])
+        .collect();
+
+    let mut max_lines = options.max_lines.unwrap_or(usize::MAX);
+    if options.overflow != TextOverflow::Clip {
+        // keep as many lines as fit, but always at least one
+        let mut height = 0;
+        let fitting = metrics
+            .iter()
+            .take_while(|metrics| {
+                height += metrics.height();
+                height <= bounds.height()
+            })
+            .count();
+        max_lines = max_lines.min(fitting.max(1));
+    }
+    let truncated = lines.len() > max_lines;
+    lines.truncate(max_lines);
+    if options.overflow != TextOverflow::Clip {
+        let count = lines.len();
+        for (i, line) in lines.iter_mut().enumerate() {
+            // the last line also has to show that the lines after it were dropped
+            if line.width > bounds.width() || (truncated && i == count - 1) {
+                if options.overflow == TextOverflow::MiddleEllipsis && line.width > bounds.width() {
+                    line.elide_middle(runs, bounds.width());
+                } else {
+                    line.elide_end(runs, bounds.width());
+                }
+            }
+        }
+    }
+
+    let width = lines
+        .iter()
+        .zip(&metrics)
+        .map(|(line, metrics)| metrics.left + line.width + metrics.right)
+        .max()
+        .unwrap_or(0);
+    let height = metrics[..lines.len()]
+        .iter()
+        .map(Metrics::height)
+        .sum::<Scalar>();
+
+    // measuring passes unbounded limits, so be careful not to overflow
+    let mut top = bounds.top().saturating_add(match options.vertical_align {
+        VerticalAlign::Top => 0,
+        VerticalAlign::Middle => (bounds.height() - height) / 2,
+        VerticalAlign::Bottom => bounds.height() - height,
+    });
+    for ((line, metrics), end) in lines.into_iter().zip(metrics).zip(ends) {
+        let start = line.start;
+        let extra = bounds.width() - (metrics.left + line.width + metrics.right);
+        let left = bounds.left().saturating_add(
+            match options.align {
+                TextAlign::Left | TextAlign::Justify => 0,
+                TextAlign::Center => extra / 2,
+                TextAlign::Right => extra,
+            } + metrics.left,
+        );
+        let baseline = top.saturating_add(metrics.ascent);
+
+        // spread the leftover space over the gaps that aren't trailing
+        let gaps = line
+            .glyphs
+            .iter()
+            .filter(|placed| placed.space && placed.x < line.width)
+            .count() as i64;
+        let justify = options.align == TextAlign::Justify && !line.end && gaps > 0;
+        let shift = |gap: i64| {
+            if justify {
+                (extra.max(0) as i64 * gap.min(gaps) / gaps) as Scalar
+            } else {
+                0
+            }
+        };
+        let mut gap = 0;
+
+        for placed in line.glyphs {
+            // a stretched space covers the gap it opens up
+            let before = shift(gap);
+            if placed.space {
+                gap += 1;
+            }
+            let after = shift(gap);
+            let x = left.saturating_add(placed.x + before);
+            let cell = Rect::new(
+                Point::new(x, top),
+                Size::new(placed.advance + after - before, metrics.height()),
+            );
+
+            // glyph offsets are from the baseline to the bottom-left of the bitmap
+            let offset = placed.glyph.offset();
+            let point = Point::new(
+                x.saturating_add(offset.x),
+                baseline - (placed.glyph.size().height() + offset.y),
+            );
+            op(GlyphBox {
+                run: placed.run,
+                glyph: placed.glyph,
+                point,
+                cell,
+                baseline,
+                missing: placed.missing,
+                index: placed.index,
+                len: placed.len,
+            });
+        }
+        line_op(
+            start..end,
+            Rect::new(
+                Point::new(left - metrics.left, top),
+                Size::new(metrics.left + line.width + metrics.right, metrics.height()),
+            ),
+            baseline,
+        );
+        top = top.saturating_add(metrics.height());
+    }
+
+    // TODO: off-by-one somewhere???
+    (width + 1, height).into()
+}
+
+impl<'a> Line<'a> {
+    /// Removes glyphs from the end until an ellipsis fits after them within `width`.
+    fn elide_end<F: Font + ?Sized>(&mut self, runs: &[Run<'a, F>], width: Scalar) {
+        // the ellipsis takes after the text it replaces
+        let run = self.glyphs.last().map_or(self.run, |placed| placed.run);
+        let ellipsis = ellipsis(&runs[run]);
+        let available = width - ellipsis_width(&runs[run], &ellipsis);
+        self.glyphs
+            .retain(|placed| placed.x + placed.advance <= available);
+        while self.glyphs.last().is_some_and(|placed| placed.space) {
+            self.glyphs.pop();
+        }
+        let end = self
+            .glyphs
+            .last()
+            .map_or(0, |placed| placed.x + placed.advance);
+        let index = self
+            .glyphs
+            .last()
+            .map_or(self.start, |placed| placed.index + placed.len);
+        self.width = place(&mut self.glyphs, &runs[run], &ellipsis, run, index, end);
+        self.end = true;
+    }
+
+    /// Removes glyphs from the middle until the start, an ellipsis and the end fit
+    /// within `width`.
+    fn elide_middle<F: Font + ?Sized>(&mut self, runs: &[Run<'a, F>], width: Scalar) {
+        let glyphs = mem::take(&mut self.glyphs);
+        let run = glyphs.first().map_or(self.run, |placed| placed.run);
+        let ellipsis = ellipsis(&runs[run]);
+        let available = width - ellipsis_width(&runs[run], &ellipsis);
+        let (mut head, mut tail): (Vec<_>, Vec<_>) = glyphs
+            .into_iter()
+            .filter(|placed| placed.x < self.width)
+            .partition(|placed| placed.x + placed.advance <= (available + 1) / 2);
+        let head_end = head.last().map_or(0, |placed| placed.x + placed.advance);
+        let tail_start = tail
+            .iter()
+            .rposition(|placed| self.width - placed.x > available - head_end)
+            .map_or(0, |i| i + 1);
+        tail.drain(..tail_start);
+
+        let run = head.last().map_or(run, |placed| placed.run);
+        let index = head
+            .last()
+            .map_or(self.start, |placed| placed.index + placed.len);
+        let after = place(&mut head, &runs[run], &ellipsis, run, index, head_end);
+        let shift = after - tail.first().map_or(self.width, |placed| placed.x);
+        for mut placed in tail {
+            placed.x += shift;
+            head.push(placed);
+        }
+        self.glyphs = head;
+        self.width += shift;
+        self.end = true;
+    }
+}
+
+/// The glyphs used to show that text was cut short.
+fn ellipsis<'a, F: Font + ?Sized>(run: &Run<'a, F>) -> Vec<Glyph<'a>> {
+    let font = run.font;
+    match font.glyph('…') {
+        Some(glyph) => vec![glyph],
+        None => font.glyph('.').map(|dot| vec![dot; 3]).unwrap_or_default(),
+    }
+}
+
+#[inline]
+fn ellipsis_width<F: Font + ?Sized>(run: &Run<F>, ellipsis: &[Glyph]) -> Scalar {
+    ellipsis.iter().map(|glyph| run.advance(glyph)).sum()
+}
+
+/// Appends `ellipsis` to `glyphs` starting at `x` and returns where it ends. The
+/// ellipsis sits at `index` in the text, where the text it hides starts.
+fn place<'a, F: Font + ?Sized>(
+    glyphs: &mut Vec<Placed<'a>>,
+    style: &Run<F>,
+    ellipsis: &[Glyph<'a>],
+    run: usize,
+    index: usize,
+    mut x: Scalar,
+) -> Scalar {
+    for &glyph in ellipsis {
+        let advance = style.advance(&glyph);
+        glyphs.push(Placed {
+            glyph,
+            x,
+            advance,
+            run,
+            space: false,
+            missing: None,
+            index,
+            len: 0,
+        });
+        x += advance;
+    }
+    x
+}
+
+const SOFT_HYPHEN: char = '\u{AD}';
+
+/// Wraps text into lines that fit the width of `bounds`.
+struct Breaker<'r, 'a, F: ?Sized> {
+    runs: &'r [Run<'a, F>],
+    /// The offset in the joined text where each run ends.
+    ends: Vec<usize>,
+    bounds: Rect,
+    lines: Vec<Line<'a>>,
+    line: Line<'a>,
+    cursor: Scalar,
+    /// The offset of the soft hyphen that the line so far ends in, if it does. It is only
+    /// drawn if the line breaks there.
+    soft_hyphen: Option<usize>,
+    missing: MissingGlyph,
+}
+
+impl<'r, 'a, F: Font + ?Sized> Breaker<'r, 'a, F> {
+    /// The run that the char at `offset` in the joined text belongs to.
+    #[inline]
+    fn run(&self, offset: usize) -> usize {
+        self.ends
+            .partition_point(|&end| end <= offset)
+            .min(self.runs.len() - 1)
+    }
+
+    #[inline]
+    fn is_line_empty(&self) -> bool {
+        self.cursor == self.bounds.left()
+    }
+
+    #[inline]
+    fn fits(&self, width: Scalar) -> bool {
+        self.cursor + width <= self.bounds.right()
+    }
+
+    /// Finds the glyph for `c` in `run`, or whatever stands in for it if the font has
+    /// none. Chars that need a [`HexBox`] come back along with their blank glyph.
+    fn glyph(&self, c: char, run: usize) -> Option<(Glyph<'a>, Option<char>)> {
+        let font = self.runs[run].font;
+        if let Some(glyph) = font.glyph(c) {
+            return Some((glyph, None));
+        }
+        match self.missing {
+            MissingGlyph::Skip => None,
+            MissingGlyph::DefaultChar => font
+                .default_char()
+                .and_then(|c| font.glyph(c))
+                .map(|glyph| (glyph, None)),
+            MissingGlyph::HexBox => Some((HexBox::new(font, c).glyph(), Some(c))),
+            MissingGlyph::Glyph(glyph) => Some((glyph, None)),
+        }
+    }
+
+    fn width(&self, text: &str, offset: usize) -> Scalar {
+        let mut width = 0;
+        for (i, c) in text.char_indices() {
+            let c = if c == SOFT_HYPHEN { '-' } else { c };
+            let run = self.run(offset + i);
+            if let Some((glyph, _)) = self.glyph(c, run) {
+                width += self.runs[run].advance(&glyph);
+            }
+        }
+        width
+    }
+
+    /// Adds the glyph for the char at `index` in the joined text to the line.
+    fn push(&mut self, c: char, index: usize, run: usize, space: bool) {
+        self.soft_hyphen = (c == SOFT_HYPHEN).then_some(index);
+        if self.soft_hyphen.is_some() {
+            return;
+        }
+        if let Some((glyph, missing)) = self.glyph(c, run) {
+            let x = self.cursor - self.bounds.left();
+            let advance = self.runs[run].advance(&glyph);
+            self.cursor += advance;
+            self.line.glyphs.push(Placed {
+                glyph,
+                x,
+                advance,
+                run,
+                space,
+                missing,
+                index,
+                len: c.len_utf8(),
+            });
+            if !space {
+                self.line.width = self.cursor - self.bounds.left();
+            }
+        }
+    }
+
+    fn push_str(&mut self, text: &str, offset: usize, space: bool) {
+        for (i, c) in text.char_indices() {
+            self.push(c, offset + i, self.run(offset + i), space);
+        }
+    }
+
+    /// Advances to the next tab stop. Like trailing spaces, a tab is dropped if its stop
+    /// is past the end of a line that may wrap.
+    fn tab(&mut self, stops: &TabStops, wrap: TextWrap, run: usize) {
+        let space = self.runs[run]
+            .font
+            .glyph(' ')
+            .map_or(0, |glyph| glyph.width());
+        let x = self.cursor - self.bounds.left();
+        let stop = match stops {
+            TabStops::Spaces(count) => next_multiple(x, space * count),
+            TabStops::Every(width) => next_multiple(x, *width),
+            TabStops::At(stops) => stops
+                .iter()
+                .copied()
+                .find(|&stop| stop > x)
+                .unwrap_or(x + space),
+        };
+        if wrap != TextWrap::None && !self.fits(stop - x) {
+            return;
+        }
+        self.cursor = self.bounds.left() + stop;
+        self.soft_hyphen = None;
+    }
+
+    /// Ends the current line. The next one starts in the run at `offset`.
+    fn break_line(&mut self, end: bool, offset: usize) {
+        if let (Some(index), false) = (self.soft_hyphen, end) {
+            let run = self
+                .line
+                .glyphs
+                .last()
+                .map_or(self.line.run, |placed| placed.run);
+            let count = self.line.glyphs.len();
+            self.push('-', index, run, false);
+            // the hyphen stands for the soft hyphen
+            if let Some(placed) = self.line.glyphs.get_mut(count) {
+                placed.len = SOFT_HYPHEN.len_utf8();
+            }
+        }
+        self.soft_hyphen = None;
+        self.line.end = end;
+        let run = self.run(offset);
+        self.lines.push(mem::replace(
+            &mut self.line,
+            Line {
+                run,
+                start: offset,
+                ..Default::default()
+            },
+        ));
+        self.cursor = self.bounds.left();
+    }
+}
+
+#[inline]
+fn next_multiple(x: Scalar, step: Scalar) -> Scalar {
+    let step = step.max(1);
+    (x / step + 1) * step
+}
+
+/// Finds the end of the next piece of `text` that can't be broken up.
+fn next_break(text: &str, wrap: TextWrap) -> usize {
+    let first = text.chars().next().unwrap_or_default();
+    if first.is_whitespace() || wrap == TextWrap::Char {
+        return first.len_utf8();
+    }
+    // a word can also break after any hyphens in it
+    text.find(|c: char| c.is_whitespace() || c == '-' || c == SOFT_HYPHEN)
+        .map_or(text.len(), |i| {
+            let c = text[i..].chars().next().unwrap_or_default();
+            if c.is_whitespace() {
+                i
+            } else {
+                i + c.len_utf8()
+            }
+        })
+}
+
+/// Wraps `runs` into lines that fit the width of `bounds`.
+///
+/// Words may carry on from one run into the next, so the runs are broken up as if they
+/// were a single string.
+fn break_lines<'a, F: Font + ?Sized>(
+    runs: &[Run<'a, F>],
+    bounds: Rect,
+    options: &TextOptions,
+) -> Vec<Line<'a>> {
+    let wrap = options.wrap;
+    let collapse = options.white_space == WhiteSpace::Collapse;
+    let text = match runs {
+        [run] => Cow::Borrowed(run.text),
+        _ => Cow::Owned(runs.iter().map(|run| run.text).collect()),
+    };
+    let mut breaker = Breaker {
+        runs,
+        ends: runs
+            .iter()
+            .scan(0, |end, run| {
+                *end += run.text.len();
+                Some(*end)
+            })
+            .collect(),
+        bounds,
+        lines: Vec::new(),
+        line: Line::default(),
+        cursor: bounds.left(),
+        soft_hyphen: None,
+        missing: options.missing_glyph,
+    };
+    breaker.line.run = breaker.run(0);
+
+    let mut rest = &text[..];
+    while let Some(first) = rest.chars().next() {
+        // consume the "word" or whatever it is
+        let offset = text.len() - rest.len();
+        let end = next_break(rest, wrap);
+        let word = &rest[..end];
+        rest = &rest[end..];
+
+        // though it might itself be a newline, in any of its spellings
+        if first == '\n' || first == '\r' {
+            if first == '\r' && rest.starts_with('\n') {
+                rest = &rest[1..];
+            }
+            breaker.break_line(true, text.len() - rest.len());
+            continue;
+        }
+
+        let space = first.is_whitespace();
+        let word = if space && collapse {
+            let after_space = breaker.line.glyphs.last().is_none_or(|placed| placed.space);
+            if breaker.is_line_empty() || after_space {
+                continue;
+            }
+            " "
+        } else {
+            word
+        };
+        if word == "\t" {
+            breaker.tab(&options.tab_stops, wrap, breaker.run(offset));
+            continue;
+        }
+        let width = breaker.width(word, offset);
+
+        // if it wont fit on this line :'(
+        if wrap != TextWrap::None && !breaker.fits(width) {
+            // we dont care about trailing spaces though...
+            if space {
+                continue;
+            }
+            if !breaker.is_line_empty() {
+                breaker.break_line(false, offset);
+            }
+
+            // a word too long for any line gets broken wherever it has to be
+            if wrap == TextWrap::WordOrChar && !breaker.fits(width) {
+                for (i, c) in word.char_indices() {
+                    let width = breaker.width(c.encode_utf8(&mut [0; 4]), offset + i);
+                    if !breaker.is_line_empty() && !breaker.fits(width) {
+                        breaker.break_line(false, offset + i);
+                    }
+                    breaker.push(c, offset + i, breaker.run(offset + i), false);
+                }
+                continue;
+            }
+        }
+
+        breaker.push_str(word, offset, space);
+    }
+
+    breaker.break_line(true, text.len());
+    breaker.lines
+}
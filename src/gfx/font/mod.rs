@@ -0,0 +1,154 @@
+mod bdf;
+mod cache;
+#[cfg(feature = "default-font")]
+mod default_font;
+mod error;
+mod glyph;
+mod hex;
+mod layout;
+mod options;
+mod paint;
+mod pcf;
+mod psf;
+mod scaled;
+mod stack;
+mod static_font;
+mod text_layout;
+
+pub use bdf::*;
+pub use cache::*;
+#[cfg(feature = "default-font")]
+pub use default_font::*;
+pub use error::*;
+pub use glyph::*;
+pub use options::*;
+pub use pcf::*;
+pub use psf::*;
+pub use scaled::*;
+pub use stack::*;
+pub use static_font::*;
+pub use text_layout::*;
+
+pub(crate) use self::{
+    hex::HexBox,
+    layout::{layout, layout_lines, GlyphBox, Run},
+    paint::paint,
+};
+use crate::gfx::{BlendMode, Color, Rect, Scalar, Size, WriteSurface};
+
+pub trait Font {
+    fn line_height(&self) -> Scalar;
+
+    /// Distance from the baseline to the top of the tallest glyphs.
+    fn ascent(&self) -> Scalar;
+
+    /// Distance from the baseline to the bottom of the lowest descenders.
+    fn descent(&self) -> Scalar;
+
+    /// Distance from the top of a line to its baseline.
+    #[inline]
+    fn baseline(&self) -> Scalar {
+        self.ascent()
+    }
+
+    fn glyph(&self, c: char) -> Option<Glyph<'_>>;
+
+    /// The char the font would rather draw in place of chars it has no glyph for. Used
+    /// by [`MissingGlyph::DefaultChar`].
+    #[inline]
+    fn default_char(&self) -> Option<char> {
+        None
+    }
+
+    /// The cache [`Font::measure`] goes through, if the font keeps one.
+    #[inline]
+    fn measure_cache(&self) -> Option<&MeasureCache> {
+        None
+    }
+
+    fn measure(&self, size: Size, text: &str, options: &TextOptions) -> Size {
+        let measure = || {
+            layout(
+                &[Run {
+                    font: self,
+                    text,
+                    style: options.style,
+                }],
+                Rect::sized(size),
+                options,
+                |_| {},
+            )
+        };
+        match self.measure_cache() {
+            Some(cache) => cache.get_or_measure(size, text, options, measure),
+            None => measure(),
+        }
+    }
+
+    /// Lays out `text` within `bounds` the way [`Font::render`] would draw it, keeping
+    /// where every glyph and line went.
+    fn layout(&self, bounds: Rect, text: &str, options: &TextOptions) -> TextLayout {
+        let runs = [Run {
+            font: self,
+            text,
+            style: options.style,
+        }];
+        TextLayout::new(&runs, bounds, options)
+    }
+
+    fn render(
+        &self,
+        bounds: Rect,
+        text: &str,
+        options: &TextOptions,
+        surface: &mut dyn WriteSurface,
+        color: Color,
+        blend: BlendMode,
+    ) {
+        let runs = [Run {
+            font: self,
+            text,
+            style: options.style,
+        }];
+        let mut boxes = Vec::new();
+        layout(&runs, bounds, options, |placed| boxes.push(placed));
+        paint(surface, bounds, &runs, &boxes, |_| (color, None), blend);
+    }
+}
+
+impl<F: Font + ?Sized> Font for &F {
+    #[inline]
+    fn line_height(&self) -> Scalar {
+        (**self).line_height()
+    }
+
+    #[inline]
+    fn ascent(&self) -> Scalar {
+        (**self).ascent()
+    }
+
+    #[inline]
+    fn descent(&self) -> Scalar {
+        (**self).descent()
+    }
+
+    #[inline]
+    fn baseline(&self) -> Scalar {
+        (**self).baseline()
+    }
+
+    #[inline]
+    fn glyph(&self, c: char) -> Option<Glyph<'_>> {
+        (**self).glyph(c)
+    }
+
+    #[inline]
+    fn default_char(&self) -> Option<char> {
+        (**self).default_char()
+    }
+
+    #[inline]
+    fn measure_cache(&self) -> Option<&MeasureCache> {
+        (**self).measure_cache()
+    }
+}
@@ -0,0 +1,142 @@
+use crate::gfx::{Color, Glyph, Scalar};
+
+/// Where each line sits horizontally within the bounds of the text.
+#[derive(Copy, Clone, Debug, Default, Eq, PartialEq, Hash)]
+pub enum TextAlign {
+    #[default]
+    Left,
+    Center,
+    Right,
+    /// Stretches the spaces of every wrapped line so that it fills the whole width. The
+    /// last line of each paragraph is left-aligned.
+    Justify,
+}
+
+/// Where the block of lines sits vertically within the bounds of the text.
+#[derive(Copy, Clone, Debug, Default, Eq, PartialEq, Hash)]
+pub enum VerticalAlign {
+    #[default]
+    Top,
+    Middle,
+    Bottom,
+}
+
+/// Where lines may break when text is too wide for its bounds.
+///
+/// Word breaks happen at whitespace and after hyphens, including soft hyphens (U+00AD),
+/// which are only drawn when a line breaks at them.
+#[derive(Copy, Clone, Debug, Default, Eq, PartialEq, Hash)]
+pub enum TextWrap {
+    /// Lines only break at newlines.
+    None,
+    #[default]
+    Word,
+    /// Lines break between any two chars.
+    Char,
+    /// Lines break between words, and words too long for a line of their own break
+    /// between chars.
+    WordOrChar,
+}
+
+/// Where tabs advance to, measured from the start of the line.
+#[derive(Clone, Debug, Eq, PartialEq, Hash)]
+pub enum TabStops {
+    /// A stop every so many widths of a space.
+    Spaces(Scalar),
+    /// A stop every so many pixels.
+    Every(Scalar),
+    /// Stops at these positions, in ascending order. Tabs past the last one advance by a
+    /// space.
+    At(Vec<Scalar>),
+}
+
+impl Default for TabStops {
+    #[inline]
+    fn default() -> Self {
+        Self::Spaces(4)
+    }
+}
+
+/// What happens to runs of whitespace within a line.
+#[derive(Copy, Clone, Debug, Default, Eq, PartialEq, Hash)]
+pub enum WhiteSpace {
+    #[default]
+    Preserve,
+    /// Every run of spaces and tabs becomes a single space, and whitespace at the start
+    /// of a line is dropped. Newlines are kept.
+    Collapse,
+}
+
+/// What happens to text that doesn't fit.
+#[derive(Copy, Clone, Debug, Default, Eq, PartialEq, Hash)]
+pub enum TextOverflow {
+    /// Lines past [`TextOptions::max_lines`] are dropped and the rest is cut off at the
+    /// bounds.
+    #[default]
+    Clip,
+    /// Like [`TextOverflow::Clip`], but lines that don't fit the bounds are dropped too,
+    /// and a line that was cut short ends with an ellipsis.
+    Ellipsis,
+    /// Like [`TextOverflow::Ellipsis`], but a line that is too wide keeps its start and
+    /// end and loses its middle instead. Handy for file paths.
+    MiddleEllipsis,
+}
+
+/// What stands in for chars that the font has no glyph for.
+#[derive(Copy, Clone, Debug, Default, Eq, PartialEq, Hash)]
+pub enum MissingGlyph {
+    /// The char is left out.
+    Skip,
+    /// The font's [`Font::default_char`](crate::gfx::Font::default_char), or nothing if
+    /// it doesn't have one.
+    #[default]
+    DefaultChar,
+    /// A box showing the char's codepoint in hex, so that it stands out.
+    HexBox,
+    Glyph(Glyph<'static>),
+}
+
+/// Effects drawn on top of a font's own glyphs, for fonts that only come in one weight.
+///
+/// Offsets are in the pixels of the glyphs, so a font scaled up 2x gets a 2px bold
+/// stroke, shadow and outline.
+#[derive(Copy, Clone, Debug, Default, Eq, PartialEq, Hash)]
+pub struct TextStyle {
+    /// Draws every glyph twice, the second time a pixel to the right, and widens its advance
+    /// to match.
+    pub bold: bool,
+    pub underline: bool,
+    pub strikethrough: bool,
+    /// A copy of the text a pixel down and to the right.
+    pub shadow: Option<Color>,
+    /// A border a pixel wide around every glyph.
+    pub outline: Option<Color>,
+}
+
+impl TextStyle {
+    /// How far the effects reach past glyphs drawn at `scale` on the left, top, right
+    /// and bottom.
+    #[inline]
+    pub(crate) fn padding(&self, scale: Scalar) -> (Scalar, Scalar, Scalar, Scalar) {
+        let outline = self.outline.is_some() as Scalar * scale;
+        let shadow = self.shadow.is_some() as Scalar * scale;
+        (outline, outline, outline.max(shadow), outline.max(shadow))
+    }
+}
+
+/// Controls how [`Font::measure`](crate::gfx::Font::measure) and
+/// [`Font::render`](crate::gfx::Font::render) lay out text.
+#[derive(Clone, Debug, Default, Eq, PartialEq, Hash)]
+pub struct TextOptions {
+    pub align: TextAlign,
+    pub vertical_align: VerticalAlign,
+    pub wrap: TextWrap,
+    pub tab_stops: TabStops,
+    pub white_space: WhiteSpace,
+    pub overflow: TextOverflow,
+    /// The most lines to lay out, if there is a limit.
+    pub max_lines: Option<usize>,
+    pub missing_glyph: MissingGlyph,
+    /// Ignored by [`RichText`](crate::ui::RichText), whose spans have their own.
+    pub style: TextStyle,
+}
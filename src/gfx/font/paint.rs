@@ -0,0 +1,141 @@
+use crate::gfx::{BlendMode, Color, Font, GlyphBox, HexBox, Point, Rect, Run, Size, WriteSurface};
+
+/// Draws the glyphs placed by [`layout`](super::layout) with the styles of their runs.
+///
+/// `colors` gives the text and background color of each run. Everything is drawn in
+/// passes, backgrounds first and decorations last, so that no glyph's shadow or outline
+/// covers its neighbour.
+pub(crate) fn paint<F, C>(
+    surface: &mut dyn WriteSurface,
+    bounds: Rect,
+    runs: &[Run<F>],
+    boxes: &[GlyphBox],
+    colors: C,
+    blend: BlendMode,
+) where
+    F: Font + ?Sized,
+    C: Fn(usize) -> (Color, Option<Color>),
+{
+    for placed in boxes {
+        if let (_, Some(background)) = colors(placed.run) {
+            fill_clipped(surface, bounds, placed.cell, background, blend);
+        }
+    }
+    for placed in boxes {
+        if let Some(shadow) = runs[placed.run].style.shadow {
+            strike(
+                surface,
+                bounds,
+                runs,
+                placed,
+                Point::new(1, 1),
+                shadow,
+                blend,
+            );
+        }
+    }
+    for placed in boxes {
+        if let Some(outline) = runs[placed.run].style.outline {
+            for y in -1..=1 {
+                for x in -1..=1 {
+                    if x != 0 || y != 0 {
+                        strike(
+                            surface,
+                            bounds,
+                            runs,
+                            placed,
+                            Point::new(x, y),
+                            outline,
+                            blend,
+                        );
+                    }
+                }
+            }
+        }
+    }
+    for placed in boxes {
+        let (color, _) = colors(placed.run);
+        strike(surface, bounds, runs, placed, Point::ZERO, color, blend);
+    }
+    for placed in boxes {
+        let Run { font, style, .. } = &runs[placed.run];
+        let (color, _) = colors(placed.run);
+        let mut decorate = |y| {
+            let line = Rect::new(
+                Point::new(placed.cell.left(), y),
+                Size::new(placed.cell.width(), 1),
+            );
+            fill_clipped(surface, bounds, line, color, blend);
+        };
+        // keep the lines within the font's own descender and x-height
+        if style.underline {
+            decorate(placed.baseline + font.descent() / 2);
+        }
+        if style.strikethrough {
+            decorate(placed.baseline - (font.ascent() + 1) / 3);
+        }
+    }
+}
+
+/// Blits a glyph `offset` from where it was placed, twice if it is bold. The offset is
+/// in the glyph's own pixels, so it grows with the glyph's scale.
+fn strike<F: Font + ?Sized>(
+    surface: &mut dyn WriteSurface,
+    bounds: Rect,
+    runs: &[Run<F>],
+    placed: &GlyphBox,
+    offset: Point,
+    color: Color,
+    blend: BlendMode,
+) {
+    let glyph = &placed.glyph;
+    let hex = placed
+        .missing
+        .map(|c| HexBox::new(runs[placed.run].font, c));
+    let mut blit = |point: Point| {
+        if let Some(hex) = &hex {
+            hex.draw(surface, bounds, point, color, blend);
+            return;
+        }
+        let visible = Rect::new(point, glyph.size()).intersect(bounds);
+        let scale = glyph.scale();
+        if scale == 1 {
+            let from = Rect::new(visible.origin - point, visible.size);
+            surface.blit_mask(from, point, glyph.bitmap(), color, blend);
+            return;
+        }
+        // scaled glyphs draw every set bit as a block
+        let bitmap = glyph.bitmap();
+        let size = bitmap.size();
+        for y in 0..size.height {
+            for x in 0..size.width {
+                if bitmap.get(Point::new(x, y)) {
+                    let block = Rect::new(
+                        point + Point::new(x * scale, y * scale),
+                        Size::new(scale, scale),
+                    );
+                    fill_clipped(surface, visible, block, color, blend);
+                }
+            }
+        }
+    };
+    let point = placed.point + offset * glyph.scale();
+    blit(point);
+    if runs[placed.run].style.bold {
+        blit(point + Point::new(glyph.scale(), 0));
+    }
+}
+
+#[inline]
+fn fill_clipped(
+    surface: &mut dyn WriteSurface,
+    bounds: Rect,
+    rect: Rect,
+    color: Color,
+    blend: BlendMode,
+) {
+    let rect = rect.intersect(bounds);
+    if !rect.is_zero() {
+        surface.fill(rect, color, blend);
+    }
+}
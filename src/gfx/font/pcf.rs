@@ -0,0 +1,580 @@
+use std::io::{self, ErrorKind, Read};
+
+use crate::gfx::{Bitmap, Font, Glyph, GlyphMap, MeasureCache, Point, Scalar, Size};
+
+const PCF_MAGIC: &[u8; 4] = b"\x01fcp";
+const GZIP_MAGIC: &[u8; 2] = b"\x1f\x8b";
+
+const PCF_ACCELERATORS: u32 = 1 << 1;
+const PCF_METRICS: u32 = 1 << 2;
+const PCF_BITMAPS: u32 = 1 << 3;
+const PCF_BDF_ENCODINGS: u32 = 1 << 5;
+const PCF_BDF_ACCELERATORS: u32 = 1 << 8;
+
+const PCF_GLYPH_PAD_MASK: u32 = 3;
+const PCF_BYTE_MASK: u32 = 1 << 2;
+const PCF_BIT_MASK: u32 = 1 << 3;
+const PCF_SCAN_UNIT_MASK: u32 = 3 << 4;
+const PCF_COMPRESSED_METRICS: u32 = 0x100;
+
+const NO_GLYPH: u16 = 0xFFFF;
+
+pub struct PcfFont {
+    line_height: Scalar,
+    ascent: Scalar,
+    descent: Scalar,
+    default_char: Option<char>,
+    glyphs: GlyphMap,
+    measure_cache: MeasureCache,
+}
+
+#[derive(Copy, Clone, Debug, Default)]
+struct Metrics {
+    left_bearing: Scalar,
+    right_bearing: Scalar,
+    width: Scalar,
+    ascent: Scalar,
+    descent: Scalar,
+}
+
+struct Table<'a> {
+    format: u32,
+    data: &'a [u8],
+    offset: usize,
+}
+
+impl<'a> Table<'a> {
+    fn find(file: &'a [u8], toc: &[(u32, u32, usize, usize)], kind: u32) -> io::Result<Self> {
+        let &(_, _, size, offset) = toc
+            .iter()
+            .find(|(table_kind, ..)| *table_kind == kind)
+            .ok_or(ErrorKind::InvalidData)?;
+        let data = file
+            .get(offset..offset.saturating_add(size))
+            .ok_or(ErrorKind::UnexpectedEof)?;
+        // each table repeats its format (always little-endian) in its first 4 bytes
+        let format = u32::from_le_bytes(
+            data.get(0..4)
+                .ok_or(ErrorKind::UnexpectedEof)?
+                .try_into()
+                .unwrap(),
+        );
+        Ok(Self {
+            format,
+            data,
+            offset: 4,
+        })
+    }
+
+    #[inline]
+    fn is_big_endian(&self) -> bool {
+        self.format & PCF_BYTE_MASK != 0
+    }
+
+    fn bytes<const N: usize>(&mut self) -> io::Result<[u8; N]> {
+        let bytes = self
+            .data
+            .get(self.offset..self.offset + N)
+            .ok_or(ErrorKind::UnexpectedEof)?;
+        self.offset += N;
+        let mut bytes: [u8; N] = bytes.try_into().unwrap();
+        if !self.is_big_endian() {
+            bytes.reverse();
+        }
+        Ok(bytes)
+    }
+
+    #[inline]
+    fn u8(&mut self) -> io::Result<u8> {
+        Ok(self.bytes::<1>()?[0])
+    }
+
+    #[inline]
+    fn i16(&mut self) -> io::Result<i16> {
+        Ok(i16::from_be_bytes(self.bytes()?))
+    }
+
+    #[inline]
+    fn u16(&mut self) -> io::Result<u16> {
+        Ok(u16::from_be_bytes(self.bytes()?))
+    }
+
+    #[inline]
+    fn i32(&mut self) -> io::Result<i32> {
+        Ok(i32::from_be_bytes(self.bytes()?))
+    }
+
+    /// Reads a count or offset, which can't be negative.
+    #[inline]
+    fn index(&mut self) -> io::Result<usize> {
+        usize::try_from(self.i32()?).map_err(|_| ErrorKind::InvalidData.into())
+    }
+
+    #[inline]
+    fn skip(&mut self, count: usize) {
+        self.offset += count;
+    }
+
+    fn metrics(&mut self, compressed: bool) -> io::Result<Metrics> {
+        if compressed {
+            let mut next = || Ok::<_, io::Error>(self.u8()? as Scalar - 0x80);
+            Ok(Metrics {
+                left_bearing: next()?,
+                right_bearing: next()?,
+                width: next()?,
+                ascent: next()?,
+                descent: next()?,
+            })
+        } else {
+            let metrics = Metrics {
+                left_bearing: self.i16()? as Scalar,
+                right_bearing: self.i16()? as Scalar,
+                width: self.i16()? as Scalar,
+                ascent: self.i16()? as Scalar,
+                descent: self.i16()? as Scalar,
+            };
+            // attributes
+            self.skip(2);
+            Ok(metrics)
+        }
+    }
+}
+
+impl PcfFont {
+    /// Parses an uncompressed PCF font.
+    ///
+    /// PCF fonts are usually installed gzipped as `.pcf.gz`, and callers must gunzip
+    /// those first. Gzipped data is rejected with an error saying so.
+    pub fn new<R: Read>(reader: &mut R) -> io::Result<Self> {
+        let mut file = Vec::new();
+        reader.read_to_end(&mut file)?;
+
+        if file.starts_with(GZIP_MAGIC) {
+            return Err(io::Error::new(
+                ErrorKind::InvalidData,
+                "PCF font is gzipped, decompress it first",
+            ));
+        }
+        if file.get(0..4) != Some(PCF_MAGIC) {
+            return Err(ErrorKind::InvalidData.into());
+        }
+
+        // the table of contents is always little-endian
+        let le_u32 = |offset: usize| -> io::Result<u32> {
+            Ok(u32::from_le_bytes(
+                file.get(offset..offset + 4)
+                    .ok_or(ErrorKind::UnexpectedEof)?
+                    .try_into()
+                    .unwrap(),
+            ))
+        };
+        let table_count = le_u32(4)? as usize;
+        let mut toc = Vec::with_capacity(table_count.min(16));
+        for i in 0..table_count {
+            let entry = 8 + i * 16;
+            toc.push((
+                le_u32(entry)?,
+                le_u32(entry + 4)?,
+                le_u32(entry + 8)? as usize,
+                le_u32(entry + 12)? as usize,
+            ));
+        }
+
+        // the BDF accelerators are more accurate when both are present
+        let mut accelerators = Table::find(&file, &toc, PCF_BDF_ACCELERATORS)
+            .or_else(|_| Table::find(&file, &toc, PCF_ACCELERATORS))?;
+        // noOverlap, constantMetrics, terminalFont, constantWidth, inkInside, inkMetrics,
+        // drawDirection and padding
+        accelerators.skip(8);
+        let ascent = accelerators.i32()?;
+        let descent = accelerators.i32()?;
+
+        let mut metrics_table = Table::find(&file, &toc, PCF_METRICS)?;
+        let compressed = metrics_table.format & PCF_COMPRESSED_METRICS != 0;
+        let metrics_count = if compressed {
+            usize::try_from(metrics_table.i16()?).map_err(|_| ErrorKind::InvalidData)?
+        } else {
+            metrics_table.index()?
+        };
+        let mut metrics = Vec::with_capacity(metrics_count.min(0x10000));
+        for _ in 0..metrics_count {
+            metrics.push(metrics_table.metrics(compressed)?);
+        }
+
+        let mut bitmaps = Table::find(&file, &toc, PCF_BITMAPS)?;
+        let glyph_count = bitmaps.index()?;
+        let mut offsets = Vec::with_capacity(glyph_count.min(0x10000));
+        for _ in 0..glyph_count {
+            offsets.push(bitmaps.index()?);
+        }
+        let mut bitmap_sizes = [0; 4];
+        for size in &mut bitmap_sizes {
+            *size = bitmaps.index()?;
+        }
+        let format = bitmaps.format;
+        let pad = 1 << (format & PCF_GLYPH_PAD_MASK);
+        let end = bitmaps
+            .offset
+            .checked_add(bitmap_sizes[(format & PCF_GLYPH_PAD_MASK) as usize])
+            .ok_or(ErrorKind::InvalidData)?;
+        let data = bitmaps
+            .data
+            .get(bitmaps.offset..end)
+            .ok_or(ErrorKind::UnexpectedEof)?;
+        let mut data = data.to_vec();
+        // normalize to most-significant-bit-first with bytes in reading order
+        if format & PCF_BIT_MASK == 0 {
+            for byte in &mut data {
+                *byte = byte.reverse_bits();
+            }
+        }
+        let scan_unit = 1 << ((format & PCF_SCAN_UNIT_MASK) >> 4);
+        if (format & PCF_BYTE_MASK != 0) != (format & PCF_BIT_MASK != 0) && scan_unit > 1 {
+            for unit in data.chunks_exact_mut(scan_unit) {
+                unit.reverse();
+            }
+        }
+
+        let mut encodings = Table::find(&file, &toc, PCF_BDF_ENCODINGS)?;
+        let mut byte = || -> io::Result<u32> {
+            u32::try_from(encodings.i16()?).map_err(|_| ErrorKind::InvalidData.into())
+        };
+        let min_byte2 = byte()?;
+        let max_byte2 = byte()?;
+        let min_byte1 = byte()?;
+        let max_byte1 = byte()?;
+        let default_char = char::from_u32(encodings.u16()? as u32);
+
+        let mut glyphs = GlyphMap::default();
+        for byte1 in min_byte1..=max_byte1 {
+            for byte2 in min_byte2..=max_byte2 {
+                let index = encodings.u16()?;
+                if index == NO_GLYPH {
+                    continue;
+                }
+                let index = index as usize;
+                let (Some(metrics), Some(&offset)) = (metrics.get(index), offsets.get(index))
+                else {
+                    return Err(ErrorKind::InvalidData.into());
+                };
+                let Some(c) = char::from_u32(byte1 << 8 | byte2) else {
+                    continue;
+                };
+
+                let size = Size::new(
+                    metrics.right_bearing - metrics.left_bearing,
+                    metrics.ascent + metrics.descent,
+                );
+                if size.width < 0 || size.height < 0 {
+                    return Err(ErrorKind::InvalidData.into());
+                }
+                let stride = Bitmap::<&[u8]>::min_stride(size.width).next_multiple_of(pad);
+                let end = stride
+                    .checked_mul(size.height as usize)
+                    .and_then(|len| offset.checked_add(len))
+                    .ok_or(ErrorKind::InvalidData)?;
+                let bits = data.get(offset..end).ok_or(ErrorKind::UnexpectedEof)?;
+                let entry = glyphs.push(
+                    &Bitmap::new(bits, stride, size),
+                    Point::new(metrics.left_bearing, -metrics.descent),
+                    metrics.width,
+                );
+                glyphs.insert(c, entry);
+            }
+        }
+
+        Ok(Self {
+            line_height: ascent.checked_add(descent).ok_or(ErrorKind::InvalidData)?,
+            ascent,
+            descent,
+            default_char,
+            glyphs,
+            measure_cache: MeasureCache::default(),
+        })
+    }
+}
+
+impl Font for PcfFont {
+    #[inline]
+    fn line_height(&self) -> Scalar {
+        self.line_height
+    }
+
+    #[inline]
+    fn ascent(&self) -> Scalar {
+        self.ascent
+    }
+
+    #[inline]
+    fn descent(&self) -> Scalar {
+        self.descent
+    }
+
+    #[inline]
+    fn glyph(&self, c: char) -> Option<Glyph<'_>> {
+        self.glyphs.get(c)
+    }
+
+    #[inline]
+    fn default_char(&self) -> Option<char> {
+        self.default_char
+    }
+
+    #[inline]
+    fn measure_cache(&self) -> Option<&MeasureCache> {
+        Some(&self.measure_cache)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Glyphs as rows of `#` and `.`, with their descent, encoded at 'A' and 'B'. 'C' is
+    /// encoded without a glyph.
+    const GLYPHS: [(&[&str], Scalar); 2] = [
+        (&["#........#", ".#......#.", "##.####..#"], 0),
+        (&["#..", ".#.", "..#", "###"], 1),
+    ];
+
+    /// Builds a PCF font with the tables in `format`, optionally corrupting the count of
+    /// metrics, the descent of the first glyph or the offset of its bitmap.
+    #[derive(Default)]
+    struct Fixture {
+        format: u32,
+        compressed: bool,
+        metrics_count: Option<i64>,
+        first_descent: Option<i64>,
+        first_offset: Option<i64>,
+    }
+
+    impl Fixture {
+        fn int(&self, out: &mut Vec<u8>, value: i64, len: usize) {
+            let bytes = &value.to_be_bytes()[8 - len..];
+            if self.format & PCF_BYTE_MASK != 0 {
+                out.extend_from_slice(bytes);
+            } else {
+                out.extend(bytes.iter().rev());
+            }
+        }
+
+        fn table(&self, format: u32) -> Vec<u8> {
+            format.to_le_bytes().to_vec()
+        }
+
+        fn build(&self) -> Vec<u8> {
+            let mut accelerators = self.table(self.format);
+            accelerators.extend_from_slice(&[0; 8]);
+            self.int(&mut accelerators, 4, 4);
+            self.int(&mut accelerators, 1, 4);
+
+            let metrics_format = if self.compressed {
+                self.format | PCF_COMPRESSED_METRICS
+            } else {
+                self.format
+            };
+            let mut metrics = self.table(metrics_format);
+            let count = self.metrics_count.unwrap_or(GLYPHS.len() as i64);
+            self.int(&mut metrics, count, if self.compressed { 2 } else { 4 });
+            for (i, (rows, descent)) in GLYPHS.into_iter().enumerate() {
+                let width = rows[0].len() as i64;
+                let ascent = rows.len() as i64 - descent as i64;
+                let descent = match self.first_descent {
+                    Some(descent) if i == 0 => descent,
+                    _ => descent as i64,
+                };
+                for value in [0, width, width + 1, ascent, descent] {
+                    if self.compressed {
+                        metrics.push((value + 0x80) as u8);
+                    } else {
+                        self.int(&mut metrics, value, 2);
+                    }
+                }
+                if !self.compressed {
+                    self.int(&mut metrics, 0, 2);
+                }
+            }
+
+            let pad = 1 << (self.format & PCF_GLYPH_PAD_MASK);
+            let scan_unit = 1 << ((self.format & PCF_SCAN_UNIT_MASK) >> 4);
+            let mut offsets = Vec::new();
+            let mut data = Vec::new();
+            for (rows, _) in GLYPHS {
+                offsets.push(data.len() as i64);
+                let stride =
+                    Bitmap::<&[u8]>::min_stride(rows[0].len() as Scalar).next_multiple_of(pad);
+                for row in rows {
+                    let mut bytes = vec![0u8; stride];
+                    for (x, pixel) in row.chars().enumerate() {
+                        if pixel == '#' {
+                            bytes[x / 8] |= 0x80 >> (x % 8);
+                        }
+                    }
+                    data.extend(bytes);
+                }
+            }
+            // the reverse of how the parser normalizes the bits
+            if self.format & PCF_BIT_MASK == 0 {
+                for byte in &mut data {
+                    *byte = byte.reverse_bits();
+                }
+            }
+            if (self.format & PCF_BYTE_MASK != 0) != (self.format & PCF_BIT_MASK != 0) {
+                for unit in data.chunks_exact_mut(scan_unit) {
+                    unit.reverse();
+                }
+            }
+            if let Some(offset) = self.first_offset {
+                offsets[0] = offset;
+            }
+            let mut bitmaps = self.table(self.format);
+            self.int(&mut bitmaps, offsets.len() as i64, 4);
+            for offset in offsets {
+                self.int(&mut bitmaps, offset, 4);
+            }
+            for _ in 0..4 {
+                self.int(&mut bitmaps, data.len() as i64, 4);
+            }
+            bitmaps.extend(data);
+
+            let mut encodings = self.table(self.format);
+            for value in [0x41, 0x43, 0, 0, 0x41, 0, 1, NO_GLYPH as i64] {
+                self.int(&mut encodings, value, 2);
+            }
+
+            let tables = [
+                (PCF_ACCELERATORS, accelerators),
+                (PCF_METRICS, metrics),
+                (PCF_BITMAPS, bitmaps),
+                (PCF_BDF_ENCODINGS, encodings),
+            ];
+            let mut file = PCF_MAGIC.to_vec();
+            file.extend_from_slice(&(tables.len() as u32).to_le_bytes());
+            let mut offset = 8 + tables.len() * 16;
+            for (kind, table) in &tables {
+                let format = u32::from_le_bytes(table[..4].try_into().unwrap());
+                for value in [*kind, format, table.len() as u32, offset as u32] {
+                    file.extend_from_slice(&value.to_le_bytes());
+                }
+                offset += table.len();
+            }
+            for (_, table) in tables {
+                file.extend(table);
+            }
+            file
+        }
+
+        fn parse(&self) -> io::Result<PcfFont> {
+            PcfFont::new(&mut &self.build()[..])
+        }
+    }
+
+    fn assert_glyphs(font: &PcfFont) {
+        assert_eq!(font.line_height(), 5);
+        assert_eq!(font.ascent(), 4);
+        assert_eq!(font.descent(), 1);
+        assert_eq!(font.default_char(), Some('A'));
+        for (c, (rows, descent)) in ['A', 'B'].into_iter().zip(GLYPHS) {
+            let glyph = font.glyph(c).unwrap();
+            let width = rows[0].len() as Scalar;
+            assert_eq!(glyph.size(), Size::new(width, rows.len() as Scalar));
+            assert_eq!(glyph.offset(), Point::new(0, -descent));
+            assert_eq!(glyph.width(), width + 1);
+            for (y, row) in rows.iter().enumerate() {
+                for (x, pixel) in row.chars().enumerate() {
+                    let point = Point::new(x as Scalar, y as Scalar);
+                    assert_eq!(
+                        glyph.bitmap().get(point),
+                        pixel == '#',
+                        "{c:?} at {point:?}"
+                    );
+                }
+            }
+        }
+        assert!(font.glyph('C').is_none());
+    }
+
+    #[test]
+    fn big_and_little_endian_compressed_metrics_agree() {
+        // bit and byte orders that differ, so that 4 byte scan units get swapped
+        let big = Fixture {
+            format: PCF_BYTE_MASK | 2 | 2 << 4,
+            compressed: true,
+            ..Fixture::default()
+        };
+        let little = Fixture {
+            format: PCF_BIT_MASK | 2 | 2 << 4,
+            compressed: true,
+            ..Fixture::default()
+        };
+        let (big, little) = (big.parse().unwrap(), little.parse().unwrap());
+        assert_glyphs(&big);
+        assert_glyphs(&little);
+        for c in ['A', 'B'] {
+            assert_eq!(big.glyph(c), little.glyph(c));
+        }
+    }
+
+    #[test]
+    fn reads_every_byte_and_bit_order() {
+        for byte_order in [0, PCF_BYTE_MASK] {
+            for bit_order in [0, PCF_BIT_MASK] {
+                for pad in 0..3 {
+                    // scan units never span more than the padding
+                    for scan_unit in 0..=pad {
+                        for compressed in [false, true] {
+                            let fixture = Fixture {
+                                format: byte_order | bit_order | pad | scan_unit << 4,
+                                compressed,
+                                ..Fixture::default()
+                            };
+                            assert_glyphs(&fixture.parse().unwrap());
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn rejects_negative_counts_and_offsets() {
+        let negative_count = Fixture {
+            compressed: true,
+            metrics_count: Some(-1),
+            ..Fixture::default()
+        };
+        let err = negative_count.parse().err().unwrap();
+        assert_eq!(err.kind(), ErrorKind::InvalidData);
+
+        let negative_offset = Fixture {
+            first_offset: Some(-1),
+            ..Fixture::default()
+        };
+        let err = negative_offset.parse().err().unwrap();
+        assert_eq!(err.kind(), ErrorKind::InvalidData);
+
+        let huge_offset = Fixture {
+            first_offset: Some(i32::MAX as i64),
+            ..Fixture::default()
+        };
+        assert!(huge_offset.parse().is_err());
+
+        // an ascent and descent that add up to a negative height
+        for compressed in [false, true] {
+            let negative_height = Fixture {
+                compressed,
+                first_descent: Some(-10),
+                ..Fixture::default()
+            };
+            let err = negative_height.parse().err().unwrap();
+            assert_eq!(err.kind(), ErrorKind::InvalidData);
+        }
+    }
+
+    #[test]
+    fn rejects_gzipped_fonts() {
+        let err = PcfFont::new(&mut &b"\x1f\x8b\x08\x00"[..]).err().unwrap();
+        assert_eq!(err.kind(), ErrorKind::InvalidData);
+        assert!(err.to_string().contains("gzipped"));
+    }
+}
@@ -0,0 +1,352 @@
+use std::{
+    io::{self, ErrorKind, Read},
+    str,
+};
+
+use crate::gfx::{Bitmap, Font, Glyph, GlyphMap, MeasureCache, Point, Scalar, Size};
+
+const PSF1_MAGIC: [u8; 2] = [0x36, 0x04];
+const PSF1_MODE512: u8 = 0x01;
+const PSF1_MODEHASTAB: u8 = 0x02;
+const PSF1_MODEHASSEQ: u8 = 0x04;
+const PSF1_SEPARATOR: u16 = 0xFFFF;
+const PSF1_STARTSEQ: u16 = 0xFFFE;
+
+const PSF2_MAGIC: [u8; 4] = [0x72, 0xB5, 0x4A, 0x86];
+const PSF2_HAS_UNICODE_TABLE: u32 = 0x01;
+const PSF2_SEPARATOR: u8 = 0xFF;
+const PSF2_STARTSEQ: u8 = 0xFE;
+
+/// A Linux console font in either the PSF1 or PSF2 format.
+pub struct PsfFont {
+    line_height: Scalar,
+    descent: Scalar,
+    glyphs: GlyphMap,
+    measure_cache: MeasureCache,
+}
+
+fn le_u32(file: &[u8], offset: usize) -> io::Result<u32> {
+    Ok(u32::from_le_bytes(
+        file.get(offset..offset + 4)
+            .ok_or(ErrorKind::UnexpectedEof)?
+            .try_into()
+            .unwrap(),
+    ))
+}
+
+impl PsfFont {
+    pub fn new<R: Read>(reader: &mut R) -> io::Result<Self> {
+        let mut file = Vec::new();
+        reader.read_to_end(&mut file)?;
+
+        let (size, count, glyph_len, bitmaps, table) = if file.starts_with(&PSF1_MAGIC) {
+            let mode = *file.get(2).ok_or(ErrorKind::UnexpectedEof)?;
+            let height = *file.get(3).ok_or(ErrorKind::UnexpectedEof)? as usize;
+            let count = if mode & PSF1_MODE512 != 0 { 512 } else { 256 };
+            let end = 4 + count * height;
+            let bitmaps = file.get(4..end).ok_or(ErrorKind::UnexpectedEof)?;
+            let table = if mode & (PSF1_MODEHASTAB | PSF1_MODEHASSEQ) != 0 {
+                Some(Table::Psf1(&file[end..]))
+            } else {
+                None
+            };
+            (
+                Size::new(8, height as Scalar),
+                count,
+                height,
+                bitmaps,
+                table,
+            )
+        } else if file.starts_with(&PSF2_MAGIC) {
+            let header_len = le_u32(&file, 8)? as usize;
+            let flags = le_u32(&file, 12)?;
+            let count = le_u32(&file, 16)? as usize;
+            let glyph_len = le_u32(&file, 20)? as usize;
+            let height =
+                Scalar::try_from(le_u32(&file, 24)?).map_err(|_| ErrorKind::InvalidData)?;
+            let width = Scalar::try_from(le_u32(&file, 28)?).map_err(|_| ErrorKind::InvalidData)?;
+            let end = count
+                .checked_mul(glyph_len)
+                .and_then(|len| len.checked_add(header_len))
+                .ok_or(ErrorKind::InvalidData)?;
+            let bitmaps = file.get(header_len..end).ok_or(ErrorKind::UnexpectedEof)?;
+            let table = if flags & PSF2_HAS_UNICODE_TABLE != 0 {
+                Some(Table::Psf2(&file[end..]))
+            } else {
+                None
+            };
+            (Size::new(width, height), count, glyph_len, bitmaps, table)
+        } else {
+            return Err(ErrorKind::InvalidData.into());
+        };
+
+        // an empty glyph would leave nothing to split the bitmaps into
+        if size.width == 0 || size.height == 0 || glyph_len == 0 {
+            return Err(ErrorKind::InvalidData.into());
+        }
+        let stride = Bitmap::<&[u8]>::min_stride(size.width);
+        let len = stride
+            .checked_mul(size.height as usize)
+            .ok_or(ErrorKind::InvalidData)?;
+        if len > glyph_len {
+            return Err(ErrorKind::InvalidData.into());
+        }
+        let bitmaps: Vec<_> = bitmaps
+            .chunks_exact(glyph_len)
+            .map(|bits| Bitmap::new(bits, stride, size))
+            .collect();
+
+        // without a unicode table, glyphs are simply indexed by codepoint
+        let chars: Vec<Vec<char>> = match table {
+            Some(table) => table.chars(count)?,
+            None => (0..count as u32)
+                .map(|index| char::from_u32(index).into_iter().collect())
+                .collect(),
+        };
+
+        // PSF has no notion of a baseline, so assume it sits right under a capital H
+        let descent = bitmaps
+            .iter()
+            .zip(&chars)
+            .find(|(_, chars)| chars.contains(&'H'))
+            .and_then(|(bitmap, _)| {
+                (0..size.height)
+                    .rev()
+                    .find(|&y| (0..size.width).any(|x| bitmap.get((x, y).into())))
+            })
+            .map_or(0, |bottom| size.height - 1 - bottom);
+
+        let mut glyphs = GlyphMap::default();
+        for (bitmap, chars) in bitmaps.iter().zip(chars) {
+            // chars mapped to the same glyph share its bits
+            let entry = glyphs.push(bitmap, Point::new(0, -descent), size.width);
+            for c in chars {
+                glyphs.insert(c, entry);
+            }
+        }
+
+        Ok(Self {
+            line_height: size.height,
+            descent,
+            glyphs,
+            measure_cache: MeasureCache::default(),
+        })
+    }
+}
+
+enum Table<'a> {
+    Psf1(&'a [u8]),
+    Psf2(&'a [u8]),
+}
+
+impl<'a> Table<'a> {
+    /// Reads the chars mapped to each glyph.
+    ///
+    /// Multi-codepoint sequences (e.g. a letter followed by a combining accent) can't be
+    /// looked up a char at a time, so they are skipped.
+    fn chars(&self, count: usize) -> io::Result<Vec<Vec<char>>> {
+        let mut chars = Vec::with_capacity(count);
+        match self {
+            Self::Psf1(mut table) => {
+                for _ in 0..count {
+                    let mut glyph_chars = Vec::new();
+                    let mut in_sequence = false;
+                    loop {
+                        let (value, rest) = table
+                            .split_first_chunk::<2>()
+                            .ok_or(ErrorKind::UnexpectedEof)?;
+                        table = rest;
+                        match u16::from_le_bytes(*value) {
+                            PSF1_SEPARATOR => break,
+                            PSF1_STARTSEQ => in_sequence = true,
+                            value if !in_sequence => {
+                                glyph_chars.extend(char::from_u32(value as u32));
+                            }
+                            _ => {}
+                        }
+                    }
+                    chars.push(glyph_chars);
+                }
+            }
+            Self::Psf2(mut table) => {
+                for _ in 0..count {
+                    let end = table
+                        .iter()
+                        .position(|&byte| byte == PSF2_SEPARATOR)
+                        .ok_or(ErrorKind::UnexpectedEof)?;
+                    let entry = &table[..end];
+                    table = &table[end + 1..];
+                    let singles = match entry.iter().position(|&byte| byte == PSF2_STARTSEQ) {
+                        Some(start) => &entry[..start],
+                        None => entry,
+                    };
+                    let singles = str::from_utf8(singles).map_err(|_| ErrorKind::InvalidData)?;
+                    chars.push(singles.chars().collect());
+                }
+            }
+        }
+        Ok(chars)
+    }
+}
+
+impl Font for PsfFont {
+    #[inline]
+    fn line_height(&self) -> Scalar {
+        self.line_height
+    }
+
+    #[inline]
+    fn ascent(&self) -> Scalar {
+        self.line_height - self.descent
+    }
+
+    #[inline]
+    fn descent(&self) -> Scalar {
+        self.descent
+    }
+
+    #[inline]
+    fn glyph(&self, c: char) -> Option<Glyph<'_>> {
+        self.glyphs.get(c)
+    }
+
+    #[inline]
+    fn measure_cache(&self) -> Option<&MeasureCache> {
+        Some(&self.measure_cache)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn psf1(mode: u8, height: u8, table: &[u16]) -> Vec<u8> {
+        let count = if mode & PSF1_MODE512 != 0 { 512 } else { 256 };
+        let mut file = vec![PSF1_MAGIC[0], PSF1_MAGIC[1], mode, height];
+        // every row of a glyph is its index, except that 'H' sits a row above the bottom
+        for i in 0..count {
+            for y in 0..height {
+                let row = if i == 'H' as usize && y == height - 1 {
+                    0
+                } else {
+                    i as u8
+                };
+                file.push(row);
+            }
+        }
+        for value in table {
+            file.extend_from_slice(&value.to_le_bytes());
+        }
+        file
+    }
+
+    fn psf2(header: [u32; 5], glyphs: &[&[u8]], table: &[u8]) -> Vec<u8> {
+        let mut file = PSF2_MAGIC.to_vec();
+        for value in [0, 32].into_iter().chain(header) {
+            file.extend_from_slice(&value.to_le_bytes());
+        }
+        for glyph in glyphs {
+            file.extend_from_slice(glyph);
+        }
+        file.extend_from_slice(table);
+        file
+    }
+
+    fn rows(font: &PsfFont, c: char) -> Option<Vec<u8>> {
+        let glyph = font.glyph(c)?;
+        let bitmap = glyph.bitmap();
+        Some((0..glyph.size().height).map(|y| bitmap.row(y)[0]).collect())
+    }
+
+    #[test]
+    fn psf1_without_table_indexes_by_codepoint() {
+        let font = PsfFont::new(&mut &psf1(0, 4, &[])[..]).unwrap();
+        assert_eq!(font.line_height(), 4);
+        assert_eq!(font.descent(), 1);
+        assert_eq!(rows(&font, 'A'), Some(vec![0x41; 4]));
+        assert_eq!(rows(&font, 'H'), Some(vec![0x48, 0x48, 0x48, 0]));
+        assert_eq!(rows(&font, '\u{100}'), None);
+    }
+
+    #[test]
+    fn psf1_unicode_table() {
+        let mut entries = vec![vec![]; 256];
+        entries[0] = vec!['H' as u16];
+        entries[1] = vec!['A' as u16, 'Å' as u16];
+        entries[2] = vec!['B' as u16, PSF1_STARTSEQ, 'e' as u16, 0x301];
+        let table: Vec<_> = entries
+            .into_iter()
+            .flat_map(|entry| entry.into_iter().chain([PSF1_SEPARATOR]))
+            .collect();
+        let file = psf1(PSF1_MODEHASTAB | PSF1_MODEHASSEQ, 4, &table);
+        let font = PsfFont::new(&mut &file[..]).unwrap();
+        assert_eq!(rows(&font, 'A'), Some(vec![1; 4]));
+        assert_eq!(rows(&font, 'Å'), Some(vec![1; 4]));
+        assert_eq!(rows(&font, 'B'), Some(vec![2; 4]));
+        assert_eq!(rows(&font, 'e'), None);
+        assert_eq!(rows(&font, '\u{301}'), None);
+        // 'H' maps to the blank glyph 0, which leaves nothing to find the baseline by
+        assert_eq!(rows(&font, 'H'), Some(vec![0; 4]));
+        assert_eq!(font.descent(), 0);
+    }
+
+    #[test]
+    fn psf1_truncated_table() {
+        let file = psf1(PSF1_MODEHASTAB, 4, &[PSF1_SEPARATOR; 255]);
+        let err = PsfFont::new(&mut &file[..]).err().unwrap();
+        assert_eq!(err.kind(), ErrorKind::UnexpectedEof);
+    }
+
+    #[test]
+    fn psf2_unicode_table_with_sequences() {
+        let mut table = Vec::new();
+        table.extend_from_slice("AΩ".as_bytes());
+        table.push(PSF2_SEPARATOR);
+        table.extend_from_slice("B".as_bytes());
+        for sequence in ["e\u{301}", "x\u{302}"] {
+            table.push(PSF2_STARTSEQ);
+            table.extend_from_slice(sequence.as_bytes());
+        }
+        table.push(PSF2_SEPARATOR);
+        table.push(PSF2_STARTSEQ);
+        table.extend_from_slice("ab".as_bytes());
+        table.push(PSF2_SEPARATOR);
+
+        let glyphs: [&[u8]; 3] = [&[0xF0, 0x90, 0xF0], &[0x80, 0x40, 0x20], &[0xFC; 3]];
+        let file = psf2([PSF2_HAS_UNICODE_TABLE, 3, 3, 3, 6], &glyphs, &table);
+        let font = PsfFont::new(&mut &file[..]).unwrap();
+        assert_eq!(font.glyph('A').unwrap().size(), Size::new(6, 3));
+        assert_eq!(rows(&font, 'A'), Some(vec![0xF0, 0x90, 0xF0]));
+        assert_eq!(rows(&font, 'Ω'), Some(vec![0xF0, 0x90, 0xF0]));
+        assert_eq!(rows(&font, 'B'), Some(vec![0x80, 0x40, 0x20]));
+        for c in ['e', '\u{301}', 'x', 'a', 'b'] {
+            assert_eq!(rows(&font, c), None, "{c:?}");
+        }
+    }
+
+    #[test]
+    fn psf2_invalid_utf8_table() {
+        let table = [0xC3, PSF2_SEPARATOR];
+        let file = psf2([PSF2_HAS_UNICODE_TABLE, 1, 1, 1, 8], &[&[0]], &table);
+        let err = PsfFont::new(&mut &file[..]).err().unwrap();
+        assert_eq!(err.kind(), ErrorKind::InvalidData);
+    }
+
+    #[test]
+    fn rejects_empty_and_oversized_glyphs() {
+        let files = [
+            psf1(0, 0, &[]),
+            // glyph length, height and width of zero
+            psf2([0, 1, 0, 1, 8], &[], &[]),
+            psf2([0, 1, 1, 0, 8], &[&[0]], &[]),
+            psf2([0, 1, 1, 1, 0], &[&[0]], &[]),
+            // a height that doesn't fit in a scalar, and one too big for the glyph length
+            psf2([0, 1, 1, u32::MAX, 8], &[&[0]], &[]),
+            psf2([0, 1, 1, 2, 8], &[&[0]], &[]),
+        ];
+        for file in files {
+            let err = PsfFont::new(&mut &file[..]).err().unwrap();
+            assert_eq!(err.kind(), ErrorKind::InvalidData);
+        }
+    }
+}
@@ -0,0 +1,159 @@
+use crate::gfx::{Font, Glyph, MeasureCache, Scalar};
+
+/// Draws another font with every pixel blown up to a square block.
+pub struct ScaledFont<F> {
+    font: F,
+    scale: Scalar,
+    measure_cache: MeasureCache,
+}
+
+impl<F: Font> ScaledFont<F> {
+    /// Wraps `font` at `scale` times its size. Scales below 1 are treated as 1.
+    #[inline]
+    pub fn new(font: F, scale: Scalar) -> Self {
+        Self {
+            font,
+            scale: scale.max(1),
+            measure_cache: MeasureCache::default(),
+        }
+    }
+
+    #[inline]
+    pub fn scale(&self) -> Scalar {
+        self.scale
+    }
+
+    #[inline]
+    pub fn font(&self) -> &F {
+        &self.font
+    }
+}
+
+impl<F: Font> Font for ScaledFont<F> {
+    #[inline]
+    fn line_height(&self) -> Scalar {
+        self.font.line_height() * self.scale
+    }
+
+    #[inline]
+    fn ascent(&self) -> Scalar {
+        self.font.ascent() * self.scale
+    }
+
+    #[inline]
+    fn descent(&self) -> Scalar {
+        self.font.descent() * self.scale
+    }
+
+    #[inline]
+    fn baseline(&self) -> Scalar {
+        self.font.baseline() * self.scale
+    }
+
+    #[inline]
+    fn glyph(&self, c: char) -> Option<Glyph<'_>> {
+        self.font.glyph(c).map(|glyph| glyph.scaled(self.scale))
+    }
+
+    #[inline]
+    fn default_char(&self) -> Option<char> {
+        self.font.default_char()
+    }
+
+    #[inline]
+    fn measure_cache(&self) -> Option<&MeasureCache> {
+        Some(&self.measure_cache)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::gfx::{
+        Bitmap, BlendMode, Color, Point, Rect, Size, TextOptions, TextStyle, VecBitmap,
+    };
+
+    /// A single pixel for every glyph, with a 2px advance.
+    struct Dot;
+
+    impl Font for Dot {
+        fn line_height(&self) -> Scalar {
+            2
+        }
+
+        fn ascent(&self) -> Scalar {
+            1
+        }
+
+        fn descent(&self) -> Scalar {
+            1
+        }
+
+        fn glyph(&self, _: char) -> Option<Glyph<'_>> {
+            static BITS: [u8; 1] = [0x80];
+            Some(Glyph::new(
+                Bitmap::new(&BITS, 1, Size::new(1, 1)),
+                Point::new(0, 0),
+                2,
+            ))
+        }
+    }
+
+    fn render(font: &dyn Font, text: &str, style: TextStyle) -> (Size, Vec<String>) {
+        let options = TextOptions {
+            style,
+            ..TextOptions::default()
+        };
+        let size = Size::new(10, 8);
+        let mut surface = VecBitmap::from_size(size);
+        font.render(
+            Rect::sized(size),
+            text,
+            &options,
+            &mut surface,
+            Color::WHITE,
+            BlendMode::None,
+        );
+        let rows = (0..size.height).map(|y| {
+            let row = (0..size.width).map(|x| match surface.get(Point::new(x, y)) {
+                true => '#',
+                false => '.',
+            });
+            row.collect()
+        });
+        (font.measure(size, text, &options), rows.collect())
+    }
+
+    #[test]
+    fn styles_scale_with_the_font() {
+        // every pixel of the font is 2x2, and so is every offset of its styles
+        let font = ScaledFont::new(Dot, 2);
+        let bold = TextStyle {
+            bold: true,
+            ..TextStyle::default()
+        };
+        let (size, rows) = render(&font, "ab", bold);
+        assert_eq!(rows[..3], ["####..####", "####..####", ".........."]);
+        assert_eq!(size, Size::new(13, 4));
+
+        let shadow = TextStyle {
+            shadow: Some(Color::WHITE),
+            ..TextStyle::default()
+        };
+        let (size, rows) = render(&font, "ab", shadow);
+        let shadowed = ["##..##....", "##..##....", "..##..##..", "..##..##.."];
+        assert_eq!(rows[..5], [shadowed.as_slice(), &[".........."]].concat());
+        assert_eq!(size, Size::new(11, 6));
+
+        let outline = TextStyle {
+            outline: Some(Color::WHITE),
+            ..TextStyle::default()
+        };
+        let (size, rows) = render(&font, "a", outline);
+        assert_eq!(
+            rows,
+            [["######...."; 6].as_slice(), &[".........."; 2]].concat()
+        );
+        assert_eq!(size, Size::new(9, 8));
+    }
+}
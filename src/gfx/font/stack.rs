@@ -0,0 +1,77 @@
+use crate::gfx::{Font, Glyph, MeasureCache, Scalar};
+
+/// A list of fonts that fill in for each other, such as a primary font followed by a
+/// symbol font. Every char is drawn by the first font that has a glyph for it.
+///
+/// Lines are tall enough for all of the fonts, with their baselines lined up.
+pub struct FontStack<'a> {
+    fonts: Vec<&'a dyn Font>,
+    measure_cache: MeasureCache,
+}
+
+impl<'a> FontStack<'a> {
+    #[inline]
+    pub fn new(fonts: Vec<&'a dyn Font>) -> Self {
+        Self {
+            fonts,
+            measure_cache: MeasureCache::default(),
+        }
+    }
+
+    #[inline]
+    pub fn fonts(&self) -> &[&'a dyn Font] {
+        &self.fonts
+    }
+
+    /// Adds a font to try after all of the others.
+    #[inline]
+    pub fn push(&mut self, font: &'a dyn Font) {
+        self.fonts.push(font);
+        self.measure_cache.clear();
+    }
+
+    fn max<M: Fn(&dyn Font) -> Scalar>(&self, metric: M) -> Scalar {
+        self.fonts
+            .iter()
+            .map(|font| metric(*font))
+            .max()
+            .unwrap_or(0)
+    }
+}
+
+impl<'a> Font for FontStack<'a> {
+    #[inline]
+    fn line_height(&self) -> Scalar {
+        self.baseline() + self.max(|font| font.line_height() - font.baseline())
+    }
+
+    #[inline]
+    fn ascent(&self) -> Scalar {
+        self.max(|font| font.ascent())
+    }
+
+    #[inline]
+    fn descent(&self) -> Scalar {
+        self.max(|font| font.descent())
+    }
+
+    #[inline]
+    fn baseline(&self) -> Scalar {
+        self.max(|font| font.baseline())
+    }
+
+    #[inline]
+    fn glyph(&self, c: char) -> Option<Glyph<'_>> {
+        self.fonts.iter().find_map(|font| font.glyph(c))
+    }
+
+    #[inline]
+    fn default_char(&self) -> Option<char> {
+        self.fonts.iter().find_map(|font| font.default_char())
+    }
+
+    #[inline]
+    fn measure_cache(&self) -> Option<&MeasureCache> {
+        Some(&self.measure_cache)
+    }
+}
@@ -0,0 +1,349 @@
+use std::io::{self, ErrorKind};
+
+use fxhash::FxHashMap;
+
+use crate::gfx::{Bitmap, Font, Glyph, Point, Scalar, Size, VecBitmap};
+
+const MAGIC: &[u8; 4] = b"PXFT";
+const VERSION: u16 = 1;
+const NO_CHAR: u32 = u32::MAX;
+
+const HEADER_LEN: usize = 24;
+const ENTRY_LEN: usize = 20;
+
+#[inline]
+fn u16_at(bytes: &[u8], offset: usize) -> u16 {
+    u16::from_le_bytes([bytes[offset], bytes[offset + 1]])
+}
+
+#[inline]
+fn i16_at(bytes: &[u8], offset: usize) -> Scalar {
+    u16_at(bytes, offset) as i16 as Scalar
+}
+
+#[inline]
+fn u32_at(bytes: &[u8], offset: usize) -> u32 {
+    u32::from_le_bytes(bytes[offset..offset + 4].try_into().unwrap())
+}
+
+/// A font baked by [`StaticFont::bake`] into a compact binary format, read in place
+/// without parsing or allocating.
+///
+/// The format is little-endian throughout:
+/// ```text
+/// header  "PXFT", version: u16, 0: u16, line_height: i16, ascent: i16, descent: i16,
+///         baseline: i16, default_char: u32 (or 0xFFFFFFFF), glyph_count: u32
+/// index   one entry per glyph, in ascending order of char:
+///         char: u32, start: u32, width: i16, height: i16, offset_x: i16,
+///         offset_y: i16, advance: i16, 0: i16
+/// bitmaps rows packed most-significant-bit first, as few bytes as fit each row,
+///         with `start` counting from the first bitmap byte
+/// ```
+/// The glyphs of `char`s that look the same share their bitmap.
+pub struct StaticFont<'a> {
+    line_height: Scalar,
+    ascent: Scalar,
+    descent: Scalar,
+    baseline: Scalar,
+    default_char: Option<char>,
+    index: &'a [[u8; ENTRY_LEN]],
+    bitmaps: &'a [u8],
+}
+
+impl<'a> StaticFont<'a> {
+    /// Checks the header of a baked font, usually one from `include_bytes!`.
+    pub fn new(data: &'a [u8]) -> io::Result<Self> {
+        let header = data.get(..HEADER_LEN).ok_or(ErrorKind::UnexpectedEof)?;
+        if &header[..4] != MAGIC || u16_at(header, 4) != VERSION {
+            return Err(ErrorKind::InvalidData.into());
+        }
+        let count = u32_at(header, 20) as usize;
+        let end = count
+            .checked_mul(ENTRY_LEN)
+            .and_then(|len| len.checked_add(HEADER_LEN))
+            .ok_or(ErrorKind::InvalidData)?;
+        let (index, _) = data
+            .get(HEADER_LEN..end)
+            .ok_or(ErrorKind::UnexpectedEof)?
+            .as_chunks();
+        Ok(Self {
+            line_height: i16_at(header, 8),
+            ascent: i16_at(header, 10),
+            descent: i16_at(header, 12),
+            baseline: i16_at(header, 14),
+            default_char: char::from_u32(u32_at(header, 16)),
+            index,
+            bitmaps: &data[end..],
+        })
+    }
+
+    /// The number of glyphs in the font.
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.index.len()
+    }
+
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.index.is_empty()
+    }
+
+    /// Bakes the glyphs that `font` has for `chars` into the format read by
+    /// [`StaticFont::new`]. Pass `'\0'..=char::MAX` for every glyph it has.
+    pub fn bake<F, I>(font: &F, chars: I) -> Vec<u8>
+    where
+        F: Font + ?Sized,
+        I: IntoIterator<Item = char>,
+    {
+        let mut chars: Vec<_> = chars.into_iter().collect();
+        chars.sort_unstable();
+        chars.dedup();
+
+        let mut index = Vec::new();
+        let mut bitmaps = Vec::new();
+        let mut starts = FxHashMap::<Vec<u8>, u32>::default();
+        let mut count = 0u32;
+        for c in chars {
+            let Some(glyph) = font.glyph(c) else {
+                continue;
+            };
+            // write out scaled glyphs at their full size
+            let size = glyph.size();
+            let scale = glyph.scale();
+            let mut bitmap = VecBitmap::from_size(size);
+            for y in 0..size.height {
+                for x in 0..size.width {
+                    let point = Point::new(x / scale, y / scale);
+                    bitmap.set(Point::new(x, y), glyph.bitmap().get(point));
+                }
+            }
+            let bits = bitmap.bits().to_vec();
+            let start = *starts.entry(bits).or_insert_with_key(|bits| {
+                let start = bitmaps.len() as u32;
+                bitmaps.extend_from_slice(bits);
+                start
+            });
+
+            let offset = glyph.offset();
+            index.extend_from_slice(&(c as u32).to_le_bytes());
+            index.extend_from_slice(&start.to_le_bytes());
+            for value in [
+                size.width,
+                size.height,
+                offset.x,
+                offset.y,
+                glyph.width(),
+                0,
+            ] {
+                index.extend_from_slice(&(value as i16).to_le_bytes());
+            }
+            count += 1;
+        }
+
+        let mut data = Vec::with_capacity(HEADER_LEN + index.len() + bitmaps.len());
+        data.extend_from_slice(MAGIC);
+        data.extend_from_slice(&VERSION.to_le_bytes());
+        data.extend_from_slice(&0u16.to_le_bytes());
+        for value in [
+            font.line_height(),
+            font.ascent(),
+            font.descent(),
+            font.baseline(),
+        ] {
+            data.extend_from_slice(&(value as i16).to_le_bytes());
+        }
+        let default_char = font.default_char().map_or(NO_CHAR, |c| c as u32);
+        data.extend_from_slice(&default_char.to_le_bytes());
+        data.extend_from_slice(&count.to_le_bytes());
+        data.extend_from_slice(&index);
+        data.extend_from_slice(&bitmaps);
+        data
+    }
+}
+
+impl<'a> Font for StaticFont<'a> {
+    #[inline]
+    fn line_height(&self) -> Scalar {
+        self.line_height
+    }
+
+    #[inline]
+    fn ascent(&self) -> Scalar {
+        self.ascent
+    }
+
+    #[inline]
+    fn descent(&self) -> Scalar {
+        self.descent
+    }
+
+    #[inline]
+    fn baseline(&self) -> Scalar {
+        self.baseline
+    }
+
+    fn glyph(&self, c: char) -> Option<Glyph<'_>> {
+        let i = self
+            .index
+            .binary_search_by_key(&(c as u32), |entry| u32_at(entry, 0))
+            .ok()?;
+        let entry = &self.index[i];
+        let size = Size::new(i16_at(entry, 8), i16_at(entry, 10));
+        let stride = Bitmap::<&[u8]>::min_stride(size.width);
+        let start = u32_at(entry, 4) as usize;
+        let bits = self
+            .bitmaps
+            .get(start..start + stride * size.height.max(0) as usize)?;
+        Some(Glyph::new(
+            Bitmap::new(bits, stride, size),
+            Point::new(i16_at(entry, 12), i16_at(entry, 14)),
+            i16_at(entry, 16),
+        ))
+    }
+
+    #[inline]
+    fn default_char(&self) -> Option<char> {
+        self.default_char
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::BufReader;
+
+    use super::*;
+    use crate::gfx::{BdfFont, ScaledFont};
+
+    const SOURCE: &str = "\
+STARTFONT 2.1
+FONTBOUNDINGBOX 5 7 0 -2
+STARTPROPERTIES 3
+FONT_ASCENT 5
+FONT_DESCENT 2
+DEFAULT_CHAR 66
+ENDPROPERTIES
+CHARS 3
+STARTCHAR A
+ENCODING 65
+DWIDTH 6 0
+BBX 5 5 0 0
+BITMAP
+70
+88
+F8
+88
+88
+ENDCHAR
+STARTCHAR B
+ENCODING 66
+DWIDTH 6 0
+BBX 5 5 0 0
+BITMAP
+70
+88
+F8
+88
+88
+ENDCHAR
+STARTCHAR g
+ENCODING 103
+DWIDTH 5 0
+BBX 4 5 0 -2
+BITMAP
+70
+90
+70
+10
+60
+ENDCHAR
+ENDFONT
+";
+
+    fn source() -> BdfFont {
+        BdfFont::new(&mut BufReader::new(SOURCE.as_bytes())).unwrap()
+    }
+
+    fn assert_metrics(baked: &StaticFont, font: &dyn Font) {
+        assert_eq!(baked.line_height(), font.line_height());
+        assert_eq!(baked.ascent(), font.ascent());
+        assert_eq!(baked.descent(), font.descent());
+        assert_eq!(baked.baseline(), font.baseline());
+        assert_eq!(baked.default_char(), font.default_char());
+    }
+
+    #[test]
+    fn round_trips_glyphs() {
+        let font = source();
+        let data = StaticFont::bake(&font, '\0'..=char::MAX);
+        let baked = StaticFont::new(&data).unwrap();
+        assert_eq!(baked.len(), 3);
+        assert_metrics(&baked, &font);
+        assert_eq!(baked.default_char(), Some('B'));
+        for c in ['A', 'B', 'g'] {
+            assert_eq!(baked.glyph(c), font.glyph(c), "{c:?}");
+        }
+        assert!(baked.glyph('C').is_none());
+        assert!(baked.glyph('\0').is_none());
+    }
+
+    #[test]
+    fn shares_bitmaps_and_skips_missing_chars() {
+        let font = source();
+        let data = StaticFont::bake(&font, ['B', 'A', 'A', 'z']);
+        let baked = StaticFont::new(&data).unwrap();
+        assert_eq!(baked.len(), 2);
+        // 'A' and 'B' look the same, so their 5 rows are stored once
+        assert_eq!(data.len(), HEADER_LEN + 2 * ENTRY_LEN + 5);
+        assert_eq!(baked.glyph('A'), baked.glyph('B'));
+        assert!(baked.glyph('g').is_none());
+    }
+
+    #[test]
+    fn expands_scaled_glyphs() {
+        let font = source();
+        let scaled = ScaledFont::new(&font, 3);
+        let data = StaticFont::bake(&scaled, ['g']);
+        let baked = StaticFont::new(&data).unwrap();
+        assert_metrics(&baked, &scaled);
+        let (glyph, source) = (baked.glyph('g').unwrap(), scaled.glyph('g').unwrap());
+        assert_eq!(glyph.scale(), 1);
+        assert_eq!(glyph.size(), source.size());
+        assert_eq!(glyph.offset(), source.offset());
+        assert_eq!(glyph.width(), source.width());
+        for y in 0..glyph.size().height {
+            for x in 0..glyph.size().width {
+                let point = Point::new(x, y);
+                let source_point = Point::new(x / 3, y / 3);
+                assert_eq!(
+                    glyph.bitmap().get(point),
+                    source.bitmap().get(source_point),
+                    "{point:?}"
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn rejects_malformed_data() {
+        let data = StaticFont::bake(&source(), ['A']);
+        let err = StaticFont::new(&data[..HEADER_LEN - 1]).err().unwrap();
+        assert_eq!(err.kind(), ErrorKind::UnexpectedEof);
+        let err = StaticFont::new(&data[..HEADER_LEN + ENTRY_LEN - 1])
+            .err()
+            .unwrap();
+        assert_eq!(err.kind(), ErrorKind::UnexpectedEof);
+
+        let mut bad_magic = data.clone();
+        bad_magic[0] = b'X';
+        let err = StaticFont::new(&bad_magic).err().unwrap();
+        assert_eq!(err.kind(), ErrorKind::InvalidData);
+        let mut bad_version = data.clone();
+        bad_version[4] = 2;
+        let err = StaticFont::new(&bad_version).err().unwrap();
+        assert_eq!(err.kind(), ErrorKind::InvalidData);
+
+        // a bitmap cut short leaves its glyph out instead of reading past the end
+        let baked = StaticFont::new(&data[..data.len() - 1]).unwrap();
+        assert!(baked.glyph('A').is_none());
+    }
+}
@@ -0,0 +1,587 @@
+use std::{cell::Cell, ops::Range};
+
+use crate::gfx::{layout_lines, Font, Point, Rect, Run, Scalar, Size, TextOptions};
+
+/// Where a glyph of some laid out text went.
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub struct GlyphPosition {
+    /// The byte offset in the text of the char the glyph stands for.
+    pub index: usize,
+    /// The length in bytes of what the glyph stands for. Glyphs that stand for nothing,
+    /// like an ellipsis, have a length of zero.
+    pub len: usize,
+    /// The advance of the glyph across the whole height of its line.
+    pub rect: Rect,
+    /// The index of the glyph's line in [`TextLayout::lines`].
+    pub line: usize,
+}
+
+/// A line of some laid out text.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct LineBox {
+    /// The bytes of the text from the start of the line up to the start of the next,
+    /// including anything that wasn't drawn, like the newline or a wrapped space.
+    pub range: Range<usize>,
+    /// The box around the drawn part of the line.
+    pub rect: Rect,
+    /// The y of the baseline.
+    pub baseline: Scalar,
+    /// The line's glyphs in [`TextLayout::glyphs`].
+    pub glyphs: Range<usize>,
+}
+
+/// Where every glyph and line of some text went, for mapping between points and places
+/// in the text.
+///
+/// Places in the text are byte offsets, and a caret at offset `i` sits just before the
+/// char that starts there.
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct TextLayout {
+    size: Size,
+    glyphs: Vec<GlyphPosition>,
+    lines: Vec<LineBox>,
+}
+
+impl TextLayout {
+    /// Lays out `runs` the way they are drawn, with byte offsets into their texts joined
+    /// together.
+    pub(crate) fn new<F: Font + ?Sized>(
+        runs: &[Run<F>],
+        bounds: Rect,
+        options: &TextOptions,
+    ) -> Self {
+        let mut glyphs = Vec::new();
+        let mut lines = Vec::new();
+        let line = Cell::new(0);
+        let size = layout_lines(
+            runs,
+            bounds,
+            options,
+            |placed| {
+                glyphs.push(GlyphPosition {
+                    index: placed.index,
+                    len: placed.len,
+                    rect: placed.cell,
+                    line: line.get(),
+                })
+            },
+            |range, rect, baseline| {
+                lines.push(LineBox {
+                    range,
+                    rect,
+                    baseline,
+                    glyphs: 0..0,
+                });
+                line.set(line.get() + 1);
+            },
+        );
+        for (i, line) in lines.iter_mut().enumerate() {
+            let start = glyphs.partition_point(|glyph| glyph.line < i);
+            let end = glyphs.partition_point(|glyph| glyph.line <= i);
+            line.glyphs = start..end;
+        }
+        Self {
+            size,
+            glyphs,
+            lines,
+        }
+    }
+
+    /// The size of the text, as [`Font::measure`] would give it.
+    #[inline]
+    pub fn size(&self) -> Size {
+        self.size
+    }
+
+    #[inline]
+    pub fn glyphs(&self) -> &[GlyphPosition] {
+        &self.glyphs
+    }
+
+    #[inline]
+    pub fn lines(&self) -> &[LineBox] {
+        &self.lines
+    }
+
+    /// The line that the caret at `index` is on.
+    #[inline]
+    pub fn line_of(&self, index: usize) -> Option<usize> {
+        self.lines
+            .iter()
+            .rposition(|line| line.range.start <= index)
+    }
+
+    /// The place in the text nearest to `point`, for putting the caret where the text was
+    /// clicked. Points above or below the text go to the first or last line.
+    pub fn hit_test(&self, point: Point) -> usize {
+        let line = self
+            .lines
+            .iter()
+            .find(|line| point.y < line.rect.origin.y + line.rect.height())
+            .or(self.lines.last());
+        let Some(line) = line else {
+            return 0;
+        };
+        let glyphs = &self.glyphs[line.glyphs.clone()];
+        // before whichever glyph the point is on the left half of
+        for glyph in glyphs {
+            if point.x < glyph.rect.origin.x + glyph.rect.width() / 2 {
+                return glyph.index;
+            }
+        }
+        glyphs
+            .last()
+            .map_or(line.range.start, |glyph| glyph.index + glyph.len)
+    }
+
+    /// A 1px wide caret at `index`, as tall as its line.
+    ///
+    /// Places that weren't drawn, like collapsed spaces or text hidden by an ellipsis,
+    /// get the caret of the next glyph that was, or of the end of their line.
+    pub fn caret(&self, index: usize) -> Option<Rect> {
+        let line = &self.lines[self.line_of(index)?];
+        let glyphs = &self.glyphs[line.glyphs.clone()];
+        let x = match glyphs
+            .iter()
+            .find(|glyph| glyph.index + glyph.len.max(1) > index)
+        {
+            Some(glyph) => glyph.rect.origin.x,
+            None => glyphs.last().map_or(line.rect.origin.x, |glyph| {
+                glyph.rect.origin.x + glyph.rect.width()
+            }),
+        };
+        Some(Rect::new(
+            Point::new(x, line.rect.origin.y),
+            Size::new(1, line.rect.height()),
+        ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::gfx::{
+        Bitmap, Glyph, TabStops, TextAlign, TextOverflow, TextWrap, VerticalAlign, WhiteSpace,
+    };
+
+    /// Blank glyphs 4px wide, on 10px lines.
+    struct Mono;
+
+    impl Font for Mono {
+        fn line_height(&self) -> Scalar {
+            10
+        }
+
+        fn ascent(&self) -> Scalar {
+            8
+        }
+
+        fn descent(&self) -> Scalar {
+            2
+        }
+
+        fn glyph(&self, _: char) -> Option<Glyph<'_>> {
+            Some(Glyph::new(
+                Bitmap::new(&[], 0, Size::new(0, 0)),
+                Point::new(0, 0),
+                4,
+            ))
+        }
+    }
+
+    fn layout(text: &str, width: Scalar, options: &TextOptions) -> TextLayout {
+        Mono.layout(
+            Rect::new(Point::new(0, 0), Size::new(width, 100)),
+            text,
+            options,
+        )
+    }
+
+    fn caret_at(layout: &TextLayout, index: usize) -> (Scalar, Scalar) {
+        let caret = layout.caret(index).unwrap();
+        (caret.origin.x, caret.origin.y)
+    }
+
+    #[test]
+    fn lines_and_glyphs() {
+        let layout = layout("ab\ncd", 100, &TextOptions::default());
+        let lines: Vec<_> = layout
+            .lines()
+            .iter()
+            .map(|line| line.range.clone())
+            .collect();
+        assert_eq!(lines, [0..3, 3..5]);
+        assert_eq!(layout.lines()[1].glyphs, 2..4);
+        assert_eq!(layout.lines()[1].baseline, 18);
+        let glyphs: Vec<_> = layout
+            .glyphs()
+            .iter()
+            .map(|glyph| (glyph.index, glyph.line))
+            .collect();
+        assert_eq!(glyphs, [(0, 0), (1, 0), (3, 1), (4, 1)]);
+        assert_eq!(layout.line_of(2), Some(0));
+        assert_eq!(layout.line_of(3), Some(1));
+    }
+
+    #[test]
+    fn carets() {
+        let lines = layout("ab\ncd", 100, &TextOptions::default());
+        let carets: Vec<_> = (0..=5).map(|index| caret_at(&lines, index)).collect();
+        assert_eq!(carets, [(0, 0), (4, 0), (8, 0), (0, 10), (4, 10), (8, 10)]);
+        assert_eq!(lines.caret(0).unwrap().size, Size::new(1, 10));
+
+        // carets inside a multi-byte char stay before it
+        let accented = layout("é€", 100, &TextOptions::default());
+        let carets: Vec<_> = (0..=5).map(|index| caret_at(&accented, index).0).collect();
+        assert_eq!(carets, [0, 0, 4, 4, 4, 8]);
+
+        let empty = layout("", 100, &TextOptions::default());
+        assert_eq!(caret_at(&empty, 0), (0, 0));
+        assert!(TextLayout::default().caret(0).is_none());
+    }
+
+    #[test]
+    fn carets_of_wrapped_and_hidden_text() {
+        // the space the line wrapped at isn't drawn, so its caret ends the line
+        let wrapped = layout("aa bb", 12, &TextOptions::default());
+        let carets: Vec<_> = (0..=5).map(|index| caret_at(&wrapped, index)).collect();
+        assert_eq!(carets, [(0, 0), (4, 0), (8, 0), (0, 10), (4, 10), (8, 10)]);
+
+        let options = TextOptions {
+            wrap: TextWrap::None,
+            overflow: TextOverflow::Ellipsis,
+            ..TextOptions::default()
+        };
+        let cut = layout("abcdef\nxy", 12, &options);
+        let ellipsis = cut.glyphs()[2];
+        assert_eq!((ellipsis.index, ellipsis.len), (2, 0));
+        // text hidden by the ellipsis goes to the end of the line
+        let carets: Vec<_> = (2..=7).map(|index| caret_at(&cut, index)).collect();
+        assert_eq!(
+            carets,
+            [(8, 0), (12, 0), (12, 0), (12, 0), (12, 0), (0, 10)]
+        );
+    }
+
+    #[test]
+    fn hit_tests() {
+        let lines = layout("ab\ncd", 100, &TextOptions::default());
+        let hit = |x, y| lines.hit_test(Point::new(x, y));
+        assert_eq!(hit(1, 2), 0);
+        // past the middle of 'a' goes after it
+        assert_eq!(hit(2, 2), 1);
+        assert_eq!(hit(5, 2), 1);
+        assert_eq!(hit(7, 2), 2);
+        assert_eq!(hit(50, 2), 2);
+        assert_eq!(hit(1, 15), 3);
+        assert_eq!(hit(50, 15), 5);
+        // above and below the text
+        assert_eq!(hit(-5, -5), 0);
+        assert_eq!(hit(5, 500), 4);
+
+        assert_eq!(TextLayout::default().hit_test(Point::new(5, 5)), 0);
+        let empty = layout("", 100, &TextOptions::default());
+        assert_eq!(empty.hit_test(Point::new(5, 5)), 0);
+    }
+
+    /// Each line as the chars of its glyphs at their 4px columns, with ellipses as `…`
+    /// and hyphens drawn for soft hyphens as `-`.
+    fn rows(layout: &TextLayout, text: &str) -> Vec<String> {
+        let mut rows = Vec::new();
+        for line in layout.lines() {
+            let mut row = String::new();
+            for glyph in &layout.glyphs()[line.glyphs.clone()] {
+                let c = match text[glyph.index..].chars().next() {
+                    _ if glyph.len == 0 => '…',
+                    Some('\u{AD}') => '-',
+                    c => c.unwrap_or_default(),
+                };
+                let column = (glyph.rect.origin.x / 4) as usize;
+                while row.chars().count() < column {
+                    row.push(' ');
+                }
+                row.push(c);
+            }
+            rows.push(row);
+        }
+        rows
+    }
+
+    /// The x and width of every glyph's cell.
+    fn cells(layout: &TextLayout) -> Vec<(Scalar, Scalar)> {
+        layout
+            .glyphs()
+            .iter()
+            .map(|glyph| (glyph.rect.origin.x, glyph.rect.width()))
+            .collect()
+    }
+
+    #[test]
+    fn horizontal_alignment() {
+        let xs = |align| {
+            let options = TextOptions {
+                align,
+                ..TextOptions::default()
+            };
+            let layout = layout("ab", 20, &options);
+            assert_eq!(layout.lines().len(), 1);
+            cells(&layout)
+                .into_iter()
+                .map(|(x, _)| x)
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(xs(TextAlign::Left), [0, 4]);
+        assert_eq!(xs(TextAlign::Center), [6, 10]);
+        assert_eq!(xs(TextAlign::Right), [12, 16]);
+    }
+
+    #[test]
+    fn justify_stretches_wrapped_lines() {
+        let options = TextOptions {
+            align: TextAlign::Justify,
+            ..TextOptions::default()
+        };
+        // 5px left over on the first line, spread over its 2 spaces
+        let text = "aa bb cc dd";
+        let justified = layout(text, 37, &options);
+        assert_eq!(rows(&justified, text)[1], "dd");
+        let stretched = [
+            (0, 4),
+            (4, 4),
+            (8, 6),
+            (14, 4),
+            (18, 4),
+            (22, 7),
+            (29, 4),
+            (33, 4),
+        ];
+        assert_eq!(cells(&justified)[..8], stretched);
+        // the last line of the paragraph stays left-aligned
+        assert_eq!(cells(&justified)[9..], [(0, 4), (4, 4)]);
+
+        // as does a line that ends its paragraph with a newline
+        let text = "aa bb\ncc dd ee";
+        let justified = layout(text, 37, &options);
+        assert_eq!(
+            cells(&justified)[..5],
+            [(0, 4), (4, 4), (8, 4), (12, 4), (16, 4)]
+        );
+    }
+
+    #[test]
+    fn vertical_alignment() {
+        let tops = |vertical_align| {
+            let options = TextOptions {
+                vertical_align,
+                ..TextOptions::default()
+            };
+            let layout = layout("ab\ncd", 100, &options);
+            let tops = layout.lines().iter().map(|line| line.rect.origin.y);
+            tops.collect::<Vec<_>>()
+        };
+        assert_eq!(tops(VerticalAlign::Top), [0, 10]);
+        assert_eq!(tops(VerticalAlign::Middle), [40, 50]);
+        assert_eq!(tops(VerticalAlign::Bottom), [80, 90]);
+    }
+
+    #[test]
+    fn end_ellipsis() {
+        let options = TextOptions {
+            wrap: TextWrap::None,
+            overflow: TextOverflow::Ellipsis,
+            ..TextOptions::default()
+        };
+        let cut = layout("abcdefgh", 20, &options);
+        assert_eq!(rows(&cut, "abcdefgh"), ["abcd…"]);
+        let ellipsis = cut.glyphs()[4];
+        assert_eq!((ellipsis.index, ellipsis.len), (4, 0));
+        assert_eq!(ellipsis.rect.origin.x, 16);
+        assert_eq!(cut.size(), Size::new(21, 10));
+        assert_eq!(
+            Mono.measure(Size::new(20, 100), "abcdefgh", &options),
+            Size::new(21, 10)
+        );
+    }
+
+    #[test]
+    fn middle_ellipsis_on_a_path() {
+        let options = TextOptions {
+            wrap: TextWrap::None,
+            overflow: TextOverflow::MiddleEllipsis,
+            ..TextOptions::default()
+        };
+        let path = "dir/sub/file.txt";
+        let cut = layout(path, 36, &options);
+        assert_eq!(rows(&cut, path), ["dir/….txt"]);
+        // the end of the path is kept, after what was cut from its middle
+        let kept: Vec<_> = cut.glyphs()[4..]
+            .iter()
+            .map(|glyph| (glyph.index, glyph.len, glyph.rect.origin.x))
+            .collect();
+        assert_eq!(
+            kept,
+            [
+                (4, 0, 16),
+                (12, 1, 20),
+                (13, 1, 24),
+                (14, 1, 28),
+                (15, 1, 32)
+            ]
+        );
+        assert_eq!(cut.size(), Size::new(37, 10));
+    }
+
+    #[test]
+    fn max_lines_clip_versus_ellipsis() {
+        let text = "aa bb cc";
+        let clip = TextOptions {
+            max_lines: Some(2),
+            overflow: TextOverflow::Clip,
+            ..TextOptions::default()
+        };
+        let clipped = layout(text, 13, &clip);
+        assert_eq!(rows(&clipped, text), ["aa ", "bb "]);
+        assert_eq!(clipped.size(), Size::new(9, 20));
+
+        let ellipsis = TextOptions {
+            overflow: TextOverflow::Ellipsis,
+            ..clip
+        };
+        let cut = layout(text, 13, &ellipsis);
+        assert_eq!(rows(&cut, text), ["aa ", "bb…"]);
+        let last = cut.glyphs()[5];
+        assert_eq!((last.index, last.len, last.rect.origin.x), (5, 0, 8));
+        assert_eq!(cut.size(), Size::new(13, 20));
+    }
+
+    #[test]
+    fn measure_reflects_truncation() {
+        let text = "aa bb cc";
+        // only two of the three lines fit in 25px
+        let limits = Size::new(13, 25);
+        let ellipsis = TextOptions {
+            overflow: TextOverflow::Ellipsis,
+            ..TextOptions::default()
+        };
+        let cut = Mono.layout(Rect::sized(limits), text, &ellipsis);
+        assert_eq!(rows(&cut, text), ["aa ", "bb…"]);
+        assert_eq!(Mono.measure(limits, text, &ellipsis), Size::new(13, 20));
+
+        let clip = TextOptions {
+            overflow: TextOverflow::Clip,
+            ..TextOptions::default()
+        };
+        let clipped = Mono.layout(Rect::sized(limits), text, &clip);
+        assert_eq!(rows(&clipped, text), ["aa ", "bb ", "cc"]);
+        assert_eq!(Mono.measure(limits, text, &clip), Size::new(9, 30));
+    }
+
+    fn wrapped(text: &str, width: Scalar, wrap: TextWrap) -> TextLayout {
+        let options = TextOptions {
+            wrap,
+            ..TextOptions::default()
+        };
+        layout(text, width, &options)
+    }
+
+    fn ranges(layout: &TextLayout) -> Vec<Range<usize>> {
+        let ranges = layout.lines().iter().map(|line| line.range.clone());
+        ranges.collect()
+    }
+
+    #[test]
+    fn wrap_modes() {
+        let unwrapped = wrapped("aa bb cc", 13, TextWrap::None);
+        assert_eq!(rows(&unwrapped, "aa bb cc"), ["aa bb cc"]);
+        assert_eq!(unwrapped.size(), Size::new(33, 10));
+
+        let chars = wrapped("abcde", 13, TextWrap::Char);
+        assert_eq!(rows(&chars, "abcde"), ["abc", "de"]);
+
+        // a hex string too long for any line only breaks between chars if allowed to
+        let hex = "ab 0123456789";
+        let words_or_chars = wrapped(hex, 25, TextWrap::WordOrChar);
+        assert_eq!(rows(&words_or_chars, hex), ["ab ", "012345", "6789"]);
+        assert_eq!(ranges(&words_or_chars), [0..3, 3..9, 9..13]);
+        let words = wrapped(hex, 25, TextWrap::Word);
+        assert_eq!(rows(&words, hex), ["ab ", "0123456789"]);
+    }
+
+    #[test]
+    fn wrap_after_hyphens() {
+        let hyphenated = wrapped("well-known", 25, TextWrap::Word);
+        assert_eq!(rows(&hyphenated, "well-known"), ["well-", "known"]);
+
+        // a soft hyphen is only drawn where the line breaks at it
+        let text = "hy\u{AD}phen";
+        let broken = wrapped(text, 17, TextWrap::Word);
+        assert_eq!(rows(&broken, text), ["hy-", "phen"]);
+        assert_eq!(ranges(&broken), [0..4, 4..8]);
+        let hyphen = broken.glyphs()[2];
+        assert_eq!((hyphen.index, hyphen.len, hyphen.rect.origin.x), (2, 2, 8));
+
+        let whole = wrapped(text, 100, TextWrap::Word);
+        assert_eq!(rows(&whole, text), ["hyphen"]);
+        assert!(whole.glyphs().iter().all(|glyph| glyph.index != 2));
+    }
+
+    fn tabbed(text: &str, tab_stops: TabStops) -> Vec<(usize, Scalar)> {
+        let options = TextOptions {
+            tab_stops,
+            ..TextOptions::default()
+        };
+        let layout = layout(text, 100, &options);
+        let glyphs = layout.glyphs().iter();
+        let drawn = glyphs.filter(|glyph| &text[glyph.index..glyph.index + 1] != "\t");
+        drawn
+            .map(|glyph| (glyph.index, glyph.rect.origin.x))
+            .collect()
+    }
+
+    #[test]
+    fn tab_stops() {
+        assert_eq!(tabbed("a\tb", TabStops::Spaces(4)), [(0, 0), (2, 16)]);
+        assert_eq!(
+            tabbed("a\tb\tc", TabStops::Every(12)),
+            [(0, 0), (2, 12), (4, 24)]
+        );
+        // the last tab is past the last stop, so it advances by a space
+        assert_eq!(
+            tabbed("a\tb\tc\td", TabStops::At(vec![8, 20])),
+            [(0, 0), (2, 8), (4, 20), (6, 28)]
+        );
+    }
+
+    #[test]
+    fn line_endings() {
+        let text = "a\rb\r\nc\n";
+        let lines = layout(text, 100, &TextOptions::default());
+        assert_eq!(rows(&lines, text), ["a", "b", "c", ""]);
+        // the trailing newline starts an empty last line
+        assert_eq!(ranges(&lines), [0..2, 2..5, 5..7, 7..7]);
+        let tops: Vec<_> = lines
+            .lines()
+            .iter()
+            .map(|line| line.rect.origin.y)
+            .collect();
+        assert_eq!(tops, [0, 10, 20, 30]);
+    }
+
+    #[test]
+    fn collapsed_white_space() {
+        let options = TextOptions {
+            white_space: WhiteSpace::Collapse,
+            ..TextOptions::default()
+        };
+        let text = "  a  \t b\n  c";
+        let collapsed = layout(text, 100, &options);
+        assert_eq!(rows(&collapsed, text), ["a b", "c"]);
+        // each run of spaces and tabs is drawn as its first space
+        let glyphs: Vec<_> = collapsed
+            .glyphs()
+            .iter()
+            .map(|glyph| (glyph.index, glyph.rect.origin.x))
+            .collect();
+        assert_eq!(glyphs, [(2, 0), (3, 4), (7, 8), (11, 0)]);
+    }
+}
@@ -2,7 +2,7 @@ use std::ops::{Add, AddAssign, Div, Mul, Neg, Sub, SubAssign};
 
 pub type Scalar = i32;
 
-#[derive(Copy, Clone, Default, Debug, Eq, PartialEq)]
+#[derive(Copy, Clone, Default, Debug, Eq, PartialEq, Hash)]
 pub struct Point {
     pub x: Scalar,
     pub y: Scalar,
@@ -88,7 +88,7 @@ where
     }
 }
 
-#[derive(Copy, Clone, Default, Debug, Eq, PartialEq)]
+#[derive(Copy, Clone, Default, Debug, Eq, PartialEq, Hash)]
 pub struct Size {
     pub(crate) width: Scalar,
     pub(crate) height: Scalar,
@@ -184,7 +184,7 @@ where
     }
 }
 
-#[derive(Copy, Clone, Default, Debug, Eq, PartialEq)]
+#[derive(Copy, Clone, Default, Debug, Eq, PartialEq, Hash)]
 pub struct Rect {
     pub origin: Point,
     pub size: Size,
@@ -269,6 +269,45 @@ impl Rect {
         }
         true
     }
+
+    #[inline]
+    pub fn intersect(&self, rect: Self) -> Self {
+        let left = self.left().max(rect.left());
+        let top = self.top().max(rect.top());
+        let right = self
+            .left()
+            .saturating_add(self.width())
+            .min(rect.left().saturating_add(rect.width()));
+        let bottom = self
+            .top()
+            .saturating_add(self.height())
+            .min(rect.top().saturating_add(rect.height()));
+        if right <= left || bottom <= top {
+            return Self::ZERO;
+        }
+        Self::new(Point::new(left, top), Size::new(right - left, bottom - top))
+    }
+
+    #[inline]
+    pub fn union(&self, rect: Self) -> Self {
+        if self.width() <= 0 || self.height() <= 0 {
+            return rect;
+        }
+        if rect.width() <= 0 || rect.height() <= 0 {
+            return *self;
+        }
+        let left = self.left().min(rect.left());
+        let top = self.top().min(rect.top());
+        let right = self
+            .left()
+            .saturating_add(self.width())
+            .max(rect.left().saturating_add(rect.width()));
+        let bottom = self
+            .top()
+            .saturating_add(self.height())
+            .max(rect.top().saturating_add(rect.height()));
+        Self::new(Point::new(left, top), Size::new(right - left, bottom - top))
+    }
 }
 
 impl<S: Into<Scalar>, T: Into<Scalar>, U: Into<Scalar>, V: Into<Scalar>> From<(S, T, U, V)>
@@ -1,9 +1,15 @@
+mod bitmap;
 mod color;
+mod diff;
 mod font;
+mod gamma;
 mod geom;
 mod surface;
 
+pub use bitmap::*;
 pub use color::*;
+pub use diff::*;
 pub use font::*;
+pub use gamma::*;
 pub use geom::*;
 pub use surface::*;
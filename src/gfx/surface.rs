@@ -1,6 +1,6 @@
 use std::borrow::{Borrow, BorrowMut};
 
-use crate::gfx::{BlendMode, Color, Point, Rect, Scalar, Size};
+use crate::gfx::{Bitmap, BlendMode, Color, Point, Rect, Scalar, Size};
 
 pub trait Surface {
     fn bounds(&self) -> Rect;
@@ -13,6 +13,17 @@ pub trait ReadSurface: Surface {
 pub trait WriteSurface: Surface {
     fn write(&mut self, point: Point, color: Color, blend: BlendMode);
 
+    /// Marks the start of a group of writes belonging to a single widget.
+    ///
+    /// Most surfaces ignore this. Surfaces that track damage use it to attribute writes
+    /// to whatever is drawing inside `bounds`. Groups may be nested.
+    #[inline]
+    fn begin_group(&mut self, _bounds: Rect) {}
+
+    /// Marks the end of the group opened by the last call to [`WriteSurface::begin_group`].
+    #[inline]
+    fn end_group(&mut self) {}
+
     fn line(&mut self, from: Point, to: Point, color: Color, blend: BlendMode) {
         let dx = (to.x - from.x).abs();
         let dy = (to.y - from.y).abs();
@@ -70,6 +81,69 @@ pub trait WriteSurface: Surface {
             }
         }
     }
+
+    /// Writes `color` everywhere a bit is set in the `from` part of `mask`.
+    ///
+    /// Like [`WriteSurface::blit`], `to` is where the origin of `mask` lands.
+    fn blit_mask(
+        &mut self,
+        from: Rect,
+        to: Point,
+        mask: &Bitmap<&[u8]>,
+        color: Color,
+        blend: BlendMode,
+    ) {
+        let from = from.intersect(mask.bounds());
+        for y in from.top()..from.top() + from.height() {
+            for x in from.left()..from.left() + from.width() {
+                let point = (x, y).into();
+                if mask.get(point) {
+                    self.write(to + point, color, blend);
+                }
+            }
+        }
+    }
+}
+
+/// Shrinks the `from` part of a blit so that everything it writes lands inside `size`.
+#[inline]
+fn clip_blit(from: Rect, to: Point, size: Size) -> Rect {
+    let dest = Rect::new(to + from.origin, from.size).intersect(Rect::sized(size));
+    Rect::new(dest.origin - to, dest.size)
+}
+
+/// Calls `op` with the offset of every pixel set in the `from` part of `mask`, where
+/// `to` is where the origin of `mask` lands on a surface of `bounds` and `stride`.
+#[inline]
+fn for_each_masked<F>(
+    bounds: Rect,
+    stride: Scalar,
+    from: Rect,
+    to: Point,
+    mask: &Bitmap<&[u8]>,
+    mut op: F,
+) where
+    F: FnMut(usize),
+{
+    let from = clip_blit(from.intersect(mask.bounds()), to, bounds.size);
+    let origin = bounds.origin + to;
+    for y in from.top()..from.top() + from.height() {
+        let row = mask.row(y);
+        let line = (origin.y + y) * stride + origin.x;
+        let mut x = from.left();
+        while x < from.left() + from.width() {
+            let byte = row[x as usize / 8];
+            // most glyph bytes are empty, so skip them whole
+            if byte == 0 {
+                x = (x / 8 + 1) * 8;
+                continue;
+            }
+            if byte & (0b1000_0000 >> (x % 8)) != 0 {
+                op((line + x) as usize);
+            }
+            x += 1;
+        }
+    }
 }
 
 #[derive(Copy, Clone, Debug)]
@@ -148,6 +222,21 @@ where
         let dst = &mut self.slice.borrow_mut()[offset as usize];
         *dst = blend.blend(color, *dst);
     }
+
+    fn blit_mask(
+        &mut self,
+        from: Rect,
+        to: Point,
+        mask: &Bitmap<&[u8]>,
+        color: Color,
+        blend: BlendMode,
+    ) {
+        let slice = self.slice.borrow_mut();
+        for_each_masked(self.bounds, self.stride, from, to, mask, |offset| {
+            let dst = &mut slice[offset];
+            *dst = blend.blend(color, *dst);
+        });
+    }
 }
 
 #[derive(Copy, Clone)]
@@ -225,15 +314,26 @@ where
         }
         let point = self.bounds.origin + point;
         let offset = (point.x + point.y * self.stride) * 4;
-        let slice = self.slice.borrow_mut();
+        let slice = &mut self.slice.borrow_mut()[offset as usize..offset as usize + 4];
+        let dst = Color::new(slice[0], slice[1], slice[2], slice[3]);
+        let color = blend.blend(color, dst);
+        slice.copy_from_slice(&[color.red, color.green, color.blue, color.alpha]);
+    }
 
-        let red = &mut slice[offset as usize + 0];
-        *red = blend.blend_channel(color.red, color.alpha, *red);
-        let green = &mut slice[offset as usize + 1];
-        *green = blend.blend_channel(color.green, color.alpha, *green);
-        let blue = &mut slice[offset as usize + 2];
-        *blue = blend.blend_channel(color.blue, color.alpha, *blue);
-        let alpha = &mut slice[offset as usize + 3];
-        *alpha = blend.blend_channel(color.alpha, color.alpha, *alpha);
+    fn blit_mask(
+        &mut self,
+        from: Rect,
+        to: Point,
+        mask: &Bitmap<&[u8]>,
+        color: Color,
+        blend: BlendMode,
+    ) {
+        let slice = self.slice.borrow_mut();
+        for_each_masked(self.bounds, self.stride, from, to, mask, |offset| {
+            let slice = &mut slice[offset * 4..offset * 4 + 4];
+            let dst = Color::new(slice[0], slice[1], slice[2], slice[3]);
+            let color = blend.blend(color, dst);
+            slice.copy_from_slice(&[color.red, color.green, color.blue, color.alpha]);
+        });
     }
 }
@@ -28,6 +28,7 @@ impl<'a, I: Copy> Widget<I> for Border<'a, I> {
         cursor: Point,
         surface: &mut dyn WriteSurface,
     ) -> Option<Hit<I>> {
+        surface.begin_group(bounds);
         surface.fill(
             Rect::new(bounds.origin, Size::new(bounds.size.width, self.weight)),
             self.color,
@@ -54,6 +55,7 @@ impl<'a, I: Copy> Widget<I> for Border<'a, I> {
             self.color,
             BlendMode::Blend,
         );
+        surface.end_group();
         if let Some(child) = self.child {
             let hit = child.render(
                 bounds.inset(self.weight, self.weight, self.weight, self.weight),
@@ -0,0 +1,251 @@
+use std::hash::{Hash, Hasher};
+
+use fxhash::{FxHashMap, FxHasher};
+
+use crate::gfx::{BlendMode, Color, Point, Rect, Size, Surface, WriteSurface};
+
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+struct Group {
+    hash: u64,
+    extent: Rect,
+}
+
+/// Remembers what every group drew on the previous frame.
+///
+/// Groups are keyed by their bounds and by how many groups with the same bounds came
+/// before them in the frame, not by widget id. Ids are optional and of any type, and
+/// [`WriteSurface::begin_group`] doesn't see them. Bounds keep a group's identity stable
+/// when widgets elsewhere appear or disappear.
+#[derive(Debug, Default)]
+pub(crate) struct DamageTracker {
+    size: Size,
+    previous: Option<FxHashMap<(Rect, usize), Group>>,
+}
+
+impl DamageTracker {
+    /// Forgets the previous frame so that the next one is reported as entirely damaged.
+    #[inline]
+    pub(crate) fn reset(&mut self) {
+        self.previous = None;
+    }
+
+    /// Starts recording a frame rendered within `bounds`.
+    pub(crate) fn record<'a>(
+        &'a mut self,
+        surface: &'a mut dyn WriteSurface,
+        bounds: Rect,
+    ) -> DamageRecorder<'a> {
+        let size = surface.bounds().size;
+        if size != self.size {
+            self.size = size;
+            self.previous = None;
+        }
+        DamageRecorder {
+            surface,
+            tracker: self,
+            bounds,
+            groups: FxHashMap::default(),
+            occurrences: FxHashMap::default(),
+            stack: Vec::new(),
+        }
+    }
+}
+
+struct OpenGroup {
+    key: (Rect, usize),
+    hasher: FxHasher,
+    extent: Rect,
+}
+
+pub(crate) struct DamageRecorder<'a> {
+    surface: &'a mut dyn WriteSurface,
+    tracker: &'a mut DamageTracker,
+    bounds: Rect,
+    groups: FxHashMap<(Rect, usize), Group>,
+    occurrences: FxHashMap<Rect, usize>,
+    stack: Vec<OpenGroup>,
+}
+
+impl<'a> DamageRecorder<'a> {
+    /// Compares everything drawn since the recorder was created with the previous frame
+    /// and returns the bounding rect of all writes that differ.
+    ///
+    /// Without a previous frame, or after the surface changed size, nothing on the
+    /// surface can be trusted to be up to date, so the whole render area is returned.
+    pub(crate) fn finish(mut self) -> Option<Rect> {
+        while !self.stack.is_empty() {
+            self.end_group();
+        }
+
+        let damage = match &self.tracker.previous {
+            Some(previous) => {
+                let mut damage = Rect::ZERO;
+                for (key, group) in &self.groups {
+                    match previous.get(key) {
+                        Some(previous) if previous == group => {}
+                        Some(previous) => {
+                            damage = damage.union(previous.extent).union(group.extent)
+                        }
+                        None => damage = damage.union(group.extent),
+                    }
+                }
+                for (key, previous) in previous {
+                    if !self.groups.contains_key(key) {
+                        damage = damage.union(previous.extent);
+                    }
+                }
+                damage
+            }
+            None => self.bounds,
+        };
+        self.tracker.previous = Some(self.groups);
+
+        let damage = damage.intersect(Rect::sized(self.surface.bounds().size));
+        if damage.is_zero() {
+            None
+        } else {
+            Some(damage)
+        }
+    }
+}
+
+impl<'a> Surface for DamageRecorder<'a> {
+    #[inline]
+    fn bounds(&self) -> Rect {
+        self.surface.bounds()
+    }
+}
+
+impl<'a> WriteSurface for DamageRecorder<'a> {
+    fn write(&mut self, point: Point, color: Color, blend: BlendMode) {
+        if let Some(group) = self.stack.last_mut() {
+            point.hash(&mut group.hasher);
+            color.hash(&mut group.hasher);
+            blend.hash(&mut group.hasher);
+            group.extent = group.extent.union(Rect::new(point, (1, 1).into()));
+        }
+        self.surface.write(point, color, blend);
+    }
+
+    fn begin_group(&mut self, bounds: Rect) {
+        // groups are identified by where they are drawn, so widgets that appear or
+        // disappear don't shift the identity of everything drawn after them
+        let occurrence = self.occurrences.entry(bounds).or_insert(0);
+        let key = (bounds, *occurrence);
+        *occurrence += 1;
+        self.stack.push(OpenGroup {
+            key,
+            hasher: FxHasher::default(),
+            extent: Rect::ZERO,
+        });
+        self.surface.begin_group(bounds);
+    }
+
+    fn end_group(&mut self) {
+        if let Some(group) = self.stack.pop() {
+            self.groups.insert(
+                group.key,
+                Group {
+                    hash: group.hasher.finish(),
+                    extent: group.extent,
+                },
+            );
+            self.surface.end_group();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        gfx::{Bitmap, Font, Glyph, Scalar, VecBitmap},
+        ui::{Label, VBox, Widget, UI},
+    };
+
+    /// Solid 3x5 glyphs on 6px lines, with a 4px advance.
+    struct Blocks;
+
+    impl Font for Blocks {
+        fn line_height(&self) -> Scalar {
+            6
+        }
+
+        fn ascent(&self) -> Scalar {
+            5
+        }
+
+        fn descent(&self) -> Scalar {
+            1
+        }
+
+        fn glyph(&self, _: char) -> Option<Glyph<'_>> {
+            static BITS: [u8; 5] = [0xE0; 5];
+            Some(Glyph::new(
+                Bitmap::new(&BITS, 1, Size::new(3, 5)),
+                Point::new(0, 0),
+                4,
+            ))
+        }
+    }
+
+    fn label(text: &str) -> Label<'_, ()> {
+        Label {
+            font: Some(&Blocks),
+            text,
+            ..Label::default()
+        }
+    }
+
+    fn render(ui: &mut UI, surface: &mut VecBitmap, texts: &[&str]) -> Option<Rect> {
+        let labels: Vec<_> = texts.iter().map(|text| label(text)).collect();
+        let children: Vec<&dyn Widget<()>> = labels.iter().map(|label| label as _).collect();
+        let root = VBox {
+            id: None,
+            children: &children,
+        };
+        let bounds = Rect::sized(surface.bounds().size);
+        ui.render(bounds, surface, Point::MAX, &root).damage()
+    }
+
+    #[test]
+    fn first_frame_is_whole() {
+        let mut ui = UI::new();
+        ui.set_damage_tracking(true);
+        let mut surface = VecBitmap::from_size(Size::new(40, 30));
+        let damage = render(&mut ui, &mut surface, &["ab", "cd"]);
+        // the measured size of the two labels
+        let root = VBox {
+            id: None,
+            children: &[&label("ab") as &dyn Widget<()>, &label("cd")],
+        };
+        let size = root.measure(Size::new(40, 30));
+        assert_eq!(damage, Some(Rect::sized(size)));
+
+        ui.reset_damage();
+        assert_eq!(render(&mut ui, &mut surface, &["ab", "cd"]), damage);
+
+        // a surface of another size can't be assumed to hold the last frame
+        assert_eq!(render(&mut ui, &mut surface, &["ab", "cd"]), None);
+        let mut bigger = VecBitmap::from_size(Size::new(50, 30));
+        assert_eq!(render(&mut ui, &mut bigger, &["ab", "cd"]), damage);
+    }
+
+    #[test]
+    fn reports_only_what_changed() {
+        let mut ui = UI::new();
+        ui.set_damage_tracking(true);
+        let mut surface = VecBitmap::from_size(Size::new(40, 30));
+        render(&mut ui, &mut surface, &["ab", "cd", "ef"]);
+        assert_eq!(render(&mut ui, &mut surface, &["ab", "cd", "ef"]), None);
+
+        // the second label grows by a glyph, on the second line
+        let damage = render(&mut ui, &mut surface, &["ab", "cdx", "ef"]);
+        assert_eq!(damage, Some(Rect::new(Point::new(0, 6), Size::new(11, 5))));
+
+        // the third label goes, taking its old glyphs with it
+        let damage = render(&mut ui, &mut surface, &["ab", "cdx"]);
+        assert_eq!(damage, Some(Rect::new(Point::new(0, 12), Size::new(7, 5))));
+        assert_eq!(render(&mut ui, &mut surface, &["ab", "cdx"]), None);
+    }
+}
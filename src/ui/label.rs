@@ -1,7 +1,7 @@
 use std::cell::RefCell;
 
 use crate::{
-    gfx::{BlendMode, Color, Font, Point, Rect, Size, WriteSurface},
+    gfx::{BlendMode, Color, Font, Point, Rect, Size, TextLayout, TextOptions, WriteSurface},
     ui::{Hit, Widget},
 };
 
@@ -10,6 +10,7 @@ pub struct Label<'a, I> {
     pub font: Option<&'a dyn Font>,
     pub color: Color,
     pub text: &'a str,
+    pub options: TextOptions,
 }
 
 impl<'a, I> Default for Label<'a, I> {
@@ -20,14 +21,25 @@ impl<'a, I> Default for Label<'a, I> {
             font: None,
             color: Color::BLACK,
             text: "",
+            options: TextOptions::default(),
         }
     }
 }
 
+impl<'a, I> Label<'a, I> {
+    /// Where the text goes when the label is rendered within `bounds`, for hit-testing
+    /// and carets.
+    #[inline]
+    pub fn layout(&self, bounds: Rect) -> Option<TextLayout> {
+        let font = self.font?;
+        Some(font.layout(bounds, self.text, &self.options))
+    }
+}
+
 impl<'a, I: Copy> Widget<I> for Label<'a, I> {
     fn measure(&self, limits: Size) -> Size {
         if let Some(font) = self.font {
-            font.measure(limits, self.text)
+            font.measure(limits, self.text, &self.options)
         } else {
             Size::ZERO
         }
@@ -40,7 +52,16 @@ impl<'a, I: Copy> Widget<I> for Label<'a, I> {
         surface: &mut dyn WriteSurface,
     ) -> Option<Hit<I>> {
         if let Some(font) = self.font {
-            font.render(bounds, self.text, surface, self.color, BlendMode::Blend)
+            surface.begin_group(bounds);
+            font.render(
+                bounds,
+                self.text,
+                &self.options,
+                surface,
+                self.color,
+                BlendMode::Blend,
+            );
+            surface.end_group();
         }
         Hit::from_test(self.id, bounds, cursor)
     }
@@ -0,0 +1,246 @@
+use std::{
+    error::Error,
+    fmt::{self, Display, Formatter},
+};
+
+use crate::{
+    gfx::{Color, Font, ParseColorError},
+    ui::Span,
+};
+
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum MarkupErrorKind {
+    /// A `[` without a matching `]`.
+    UnterminatedTag,
+    UnknownTag(String),
+    /// A tag like `color` that needs a `=value`.
+    MissingValue(String),
+    InvalidColor(ParseColorError),
+    /// A closing tag that doesn't match the innermost open tag.
+    UnexpectedClose(String),
+    /// A tag that is still open at the end of the text.
+    Unclosed(String),
+}
+
+impl Display for MarkupErrorKind {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::UnterminatedTag => write!(f, "tag is missing its ']'"),
+            Self::UnknownTag(tag) => write!(f, "unknown tag {tag:?}"),
+            Self::MissingValue(tag) => write!(f, "tag {tag:?} needs a value"),
+            Self::InvalidColor(err) => write!(f, "{err}"),
+            Self::UnexpectedClose(tag) => write!(f, "unexpected closing tag {tag:?}"),
+            Self::Unclosed(tag) => write!(f, "tag {tag:?} is never closed"),
+        }
+    }
+}
+
+/// An error in a piece of markup, along with where it happened.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct MarkupError {
+    offset: usize,
+    kind: MarkupErrorKind,
+}
+
+impl MarkupError {
+    /// The byte offset of the offending tag in the markup.
+    #[inline]
+    pub fn offset(&self) -> usize {
+        self.offset
+    }
+
+    #[inline]
+    pub fn kind(&self) -> &MarkupErrorKind {
+        &self.kind
+    }
+}
+
+impl Display for MarkupError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "offset {}: {}", self.offset, self.kind)
+    }
+}
+
+impl Error for MarkupError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match &self.kind {
+            MarkupErrorKind::InvalidColor(err) => Some(err),
+            _ => None,
+        }
+    }
+}
+
+/// Turns text with inline tags into [`Span`]s for [`RichText`](crate::ui::RichText).
+///
+/// The supported tags are:
+/// ```text
+/// [color=#f00]red[/color]
+/// [bg=#ff0]yellow background[/bg]
+/// [u]underlined[/u]
+/// [s]struck through[/s]
+/// [b]bold[/b]
+/// ```
+/// Tags nest, and `[[` stands for a literal `[`.
+pub struct Markup<'a> {
+    /// The look of text outside of any tags.
+    pub base: Span<'a>,
+    /// The font used inside `[b]`. Without one, `[b]` fakes it with
+    /// [`TextStyle::bold`](crate::gfx::TextStyle::bold).
+    pub bold_font: Option<&'a dyn Font>,
+}
+
+impl<'a> Default for Markup<'a> {
+    #[inline]
+    fn default() -> Self {
+        Self {
+            base: Span::default(),
+            bold_font: None,
+        }
+    }
+}
+
+impl<'a> Markup<'a> {
+    pub fn parse(&self, markup: &'a str) -> Result<Vec<Span<'a>>, MarkupError> {
+        let mut spans = Vec::new();
+        let mut span = self.base;
+        // the open tags, along with the look to go back to when each one closes
+        let mut open: Vec<(&str, usize, Span<'a>)> = Vec::new();
+
+        let mut rest = markup;
+        while !rest.is_empty() {
+            let offset = markup.len() - rest.len();
+            let Some(start) = rest.find('[') else {
+                spans.push(Span { text: rest, ..span });
+                break;
+            };
+            if start > 0 {
+                spans.push(Span {
+                    text: &rest[..start],
+                    ..span
+                });
+            }
+            let offset = offset + start;
+            rest = &rest[start..];
+
+            // an escaped bracket is text, borrowed straight from the markup
+            if rest.starts_with("[[") {
+                spans.push(Span {
+                    text: &rest[..1],
+                    ..span
+                });
+                rest = &rest[2..];
+                continue;
+            }
+
+            let error = |kind| MarkupError { offset, kind };
+            let end = rest
+                .find(']')
+                .ok_or_else(|| error(MarkupErrorKind::UnterminatedTag))?;
+            let tag = &rest[1..end];
+            rest = &rest[end + 1..];
+
+            if let Some(name) = tag.strip_prefix('/') {
+                match open.pop() {
+                    Some((open_name, _, previous)) if open_name == name => span = previous,
+                    _ => return Err(error(MarkupErrorKind::UnexpectedClose(name.to_owned()))),
+                }
+                continue;
+            }
+
+            let (name, value) = match tag.split_once('=') {
+                Some((name, value)) => (name, Some(value)),
+                None => (tag, None),
+            };
+            let color = || -> Result<Color, MarkupError> {
+                value
+                    .ok_or_else(|| error(MarkupErrorKind::MissingValue(name.to_owned())))?
+                    .parse()
+                    .map_err(|err| error(MarkupErrorKind::InvalidColor(err)))
+            };
+            open.push((name, offset, span));
+            match name {
+                "color" => span.color = color()?,
+                "bg" => span.background = Some(color()?),
+                "u" => span.style.underline = true,
+                "s" => span.style.strikethrough = true,
+                "b" => match self.bold_font {
+                    Some(font) => span.font = Some(font),
+                    None => span.style.bold = true,
+                },
+                _ => return Err(error(MarkupErrorKind::UnknownTag(name.to_owned()))),
+            }
+        }
+
+        if let Some((name, offset, _)) = open.pop() {
+            return Err(MarkupError {
+                offset,
+                kind: MarkupErrorKind::Unclosed(name.to_owned()),
+            });
+        }
+        Ok(spans)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn error(markup: &str) -> (usize, MarkupErrorKind) {
+        let err = Markup::default().parse(markup).err().unwrap();
+        (err.offset(), err.kind)
+    }
+
+    #[test]
+    fn spans() {
+        let spans = Markup::default()
+            .parse("a [[b] [color=#f00]c[u]d[/u][/color]")
+            .unwrap();
+        let texts: Vec<_> = spans.iter().map(|span| span.text).collect();
+        assert_eq!(texts, ["a ", "[", "b] ", "c", "d"]);
+        assert_eq!(spans[2].color, Color::BLACK);
+        assert_eq!(spans[3].color, "#f00".parse().unwrap());
+        assert!(!spans[3].style.underline);
+        assert!(spans[4].style.underline);
+        assert_eq!(spans[4].color, spans[3].color);
+    }
+
+    #[test]
+    fn error_offsets() {
+        assert_eq!(error("ab[u"), (2, MarkupErrorKind::UnterminatedTag));
+        assert_eq!(
+            error("[u]x[/u][big]"),
+            (8, MarkupErrorKind::UnknownTag("big".to_owned()))
+        );
+        assert_eq!(
+            error("x[bg]"),
+            (1, MarkupErrorKind::MissingValue("bg".to_owned()))
+        );
+        assert_eq!(
+            error("[u][color=red]"),
+            (
+                3,
+                MarkupErrorKind::InvalidColor(ParseColorError::MissingHash)
+            )
+        );
+        assert_eq!(
+            error("[u]x[/s]"),
+            (4, MarkupErrorKind::UnexpectedClose("s".to_owned()))
+        );
+        // the innermost tag left open is the one reported
+        assert_eq!(
+            error("[s]x[u]y"),
+            (4, MarkupErrorKind::Unclosed("u".to_owned()))
+        );
+    }
+
+    #[test]
+    fn error_offsets_count_bytes() {
+        // escaped brackets and multi-byte chars before the tag
+        assert_eq!(
+            error("[[é…[x]"),
+            (7, MarkupErrorKind::UnknownTag("x".to_owned()))
+        );
+        let err = Markup::default().parse("é[/u]").err().unwrap();
+        assert_eq!(err.to_string(), "offset 2: unexpected closing tag \"u\"");
+    }
+}
@@ -1,10 +1,13 @@
 mod border;
+mod damage;
 mod handler;
 mod hbox;
 mod hspan;
 mod label;
 mod margin;
+mod markup;
 mod overflow;
+mod rich_text;
 mod stack;
 mod vbox;
 mod vspan;
@@ -17,14 +20,22 @@ pub use hbox::*;
 pub use hspan::*;
 pub use label::*;
 pub use margin::*;
+pub use markup::*;
 pub use overflow::*;
+pub use rich_text::*;
 pub use stack::*;
 pub use vbox::*;
 pub use vspan::*;
 
-use crate::gfx::{Point, Rect, Size, WriteSurface};
+use crate::{
+    gfx::{Point, Rect, Size, WriteSurface},
+    ui::damage::DamageTracker,
+};
 
-pub struct UI {}
+#[derive(Default)]
+pub struct UI {
+    damage: Option<DamageTracker>,
+}
 
 #[derive(Debug)]
 pub struct Hit<I> {
@@ -57,6 +68,7 @@ impl<I> Hit<I> {
 pub struct Feedback<I> {
     size: Size,
     hit: Option<Hit<I>>,
+    damage: Option<Rect>,
 }
 
 impl<I> Feedback<I> {
@@ -69,11 +81,48 @@ impl<I> Feedback<I> {
     pub fn hit(&self) -> Option<&Hit<I>> {
         self.hit.as_ref()
     }
+
+    /// The region of the surface that changed since the previous frame, or `None` if
+    /// nothing did. Without damage tracking this is always the whole render area.
+    #[inline]
+    pub fn damage(&self) -> Option<Rect> {
+        self.damage
+    }
 }
 
 impl UI {
     pub fn new() -> Self {
-        Self {}
+        Self { damage: None }
+    }
+
+    /// Enables or disables damage tracking.
+    ///
+    /// While enabled, the UI remembers what every widget drew on the previous frame and
+    /// reports only the area that changed through [`Feedback::damage`]. A tracking UI
+    /// compares consecutive calls to [`UI::render`], so use one per root widget. The
+    /// first frame, and the first after the surface changes size, report the whole
+    /// render area.
+    ///
+    /// Widgets are told apart by their bounds and the order they draw in, not by their
+    /// ids, so a widget that moves is reported both where it was and where it is now.
+    pub fn set_damage_tracking(&mut self, enabled: bool) {
+        if enabled != self.damage.is_some() {
+            self.damage = enabled.then(DamageTracker::default);
+        }
+    }
+
+    /// Forgets the previous frame so that the next one reports the whole render area as
+    /// damaged, such as after the backend cleared the screen.
+    #[inline]
+    pub fn reset_damage(&mut self) {
+        if let Some(tracker) = &mut self.damage {
+            tracker.reset();
+        }
+    }
+
+    #[inline]
+    pub fn is_tracking_damage(&self) -> bool {
+        self.damage.is_some()
     }
 
     pub fn render<I>(
@@ -84,9 +133,23 @@ impl UI {
         root: &dyn Widget<I>,
     ) -> Feedback<I> {
         let size = root.measure(bounds.size);
-        Feedback {
-            size,
-            hit: root.render(Rect::new(bounds.origin, size), cursor, surface),
+        let bounds = Rect::new(bounds.origin, size);
+        if let Some(tracker) = &mut self.damage {
+            let mut recorder = tracker.record(surface, bounds);
+            recorder.begin_group(bounds);
+            let hit = root.render(bounds, cursor, &mut recorder);
+            recorder.end_group();
+            Feedback {
+                size,
+                hit,
+                damage: recorder.finish(),
+            }
+        } else {
+            Feedback {
+                size,
+                hit: root.render(bounds, cursor, surface),
+                damage: Some(bounds),
+            }
         }
     }
 }
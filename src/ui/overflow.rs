@@ -24,6 +24,16 @@ impl<'a> WriteSurface for WriteClipper<'a> {
         }
         self.surface.write(point, color, blend);
     }
+
+    #[inline]
+    fn begin_group(&mut self, bounds: Rect) {
+        self.surface.begin_group(bounds);
+    }
+
+    #[inline]
+    fn end_group(&mut self) {
+        self.surface.end_group();
+    }
 }
 
 #[derive(Default)]
@@ -62,6 +72,7 @@ impl<'a, I: Copy> Widget<I> for Overflow<'a, I> {
             hit = child.render(inner_bounds, cursor, &mut WriteClipper { surface, bounds });
         }
 
+        surface.begin_group(bounds);
         surface.fill(
             Rect::new(
                 (bounds.right() - SLIDER_WIDTH + 1, bounds.origin.y).into(),
@@ -87,6 +98,7 @@ impl<'a, I: Copy> Widget<I> for Overflow<'a, I> {
             Color::opaque(127, 127, 127),
             BlendMode::None,
         );
+        surface.end_group();
 
         if hit.is_some() {
             return hit;
@@ -0,0 +1,105 @@
+use crate::{
+    gfx::{
+        layout, paint, BlendMode, Color, Font, Point, Rect, Run, Size, TextLayout, TextOptions,
+        TextStyle, WriteSurface,
+    },
+    ui::{Hit, Widget},
+};
+
+/// A piece of [`RichText`] with its own look.
+#[derive(Copy, Clone)]
+pub struct Span<'a> {
+    pub text: &'a str,
+    /// Falls back to [`RichText::font`] when not set.
+    pub font: Option<&'a dyn Font>,
+    pub color: Color,
+    pub background: Option<Color>,
+    pub style: TextStyle,
+}
+
+impl<'a> Default for Span<'a> {
+    #[inline]
+    fn default() -> Self {
+        Self {
+            text: "",
+            font: None,
+            color: Color::BLACK,
+            background: None,
+            style: TextStyle::default(),
+        }
+    }
+}
+
+/// Text made of spans that are laid out and wrapped together.
+pub struct RichText<'a, I> {
+    pub id: Option<I>,
+    pub font: Option<&'a dyn Font>,
+    pub spans: &'a [Span<'a>],
+    pub options: TextOptions,
+}
+
+impl<'a, I> Default for RichText<'a, I> {
+    #[inline]
+    fn default() -> Self {
+        Self {
+            id: None,
+            font: None,
+            spans: &[],
+            options: TextOptions::default(),
+        }
+    }
+}
+
+impl<'a, I> RichText<'a, I> {
+    /// Pairs every span that has a font with a run to lay out.
+    fn runs(&self) -> (Vec<&Span<'a>>, Vec<Run<'a, dyn Font + 'a>>) {
+        self.spans
+            .iter()
+            .filter_map(|span| {
+                let font = span.font.or(self.font)?;
+                Some((
+                    span,
+                    Run {
+                        font,
+                        text: span.text,
+                        style: span.style,
+                    },
+                ))
+            })
+            .unzip()
+    }
+
+    /// Where the text goes when it is rendered within `bounds`, for hit-testing and
+    /// carets. Offsets are into the texts of the spans that have a font, joined together.
+    #[inline]
+    pub fn layout(&self, bounds: Rect) -> TextLayout {
+        let (_, runs) = self.runs();
+        TextLayout::new(&runs, bounds, &self.options)
+    }
+}
+
+impl<'a, I: Copy> Widget<I> for RichText<'a, I> {
+    fn measure(&self, limits: Size) -> Size {
+        let (_, runs) = self.runs();
+        layout(&runs, Rect::sized(limits), &self.options, |_| {})
+    }
+
+    fn render(
+        &self,
+        bounds: Rect,
+        cursor: Point,
+        surface: &mut dyn WriteSurface,
+    ) -> Option<Hit<I>> {
+        let (spans, runs) = self.runs();
+        if !runs.is_empty() {
+            let mut boxes = Vec::new();
+            layout(&runs, bounds, &self.options, |placed| boxes.push(placed));
+            let colors = |run: usize| (spans[run].color, spans[run].background);
+
+            surface.begin_group(bounds);
+            paint(surface, bounds, &runs, &boxes, colors, BlendMode::Blend);
+            surface.end_group();
+        }
+        Hit::from_test(self.id, bounds, cursor)
+    }
+}